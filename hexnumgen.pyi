@@ -1,3 +1,6 @@
+import os
+from typing import Iterable, Iterator, Literal, Self, TypedDict, overload
+
 class Bounds:
     q: int
     r: int
@@ -7,6 +10,36 @@ class Bounds:
     def largest_dimension(self) -> int: ...
     @property
     def quasi_area(self) -> int: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
+
+class FractionPolicy:
+    fractional_target: bool
+    fractional_intermediates: bool
+    max_denominator: int | None
+    def __init__(
+        self,
+        fractional_target: bool = False,
+        fractional_intermediates: bool = False,
+        max_denominator: int | None = None,
+    ) -> None: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
+
+class Objective:
+    QuasiArea: Objective
+    Segments: Objective
+    LargestDimension: Objective
+    Points: Objective
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
+
+class GiveItem:
+    Focus: GiveItem
+    Spellbook: GiveItem
 
 class GeneratedNumber:
     @property
@@ -19,36 +52,171 @@ class GeneratedNumber:
     def num_points(self) -> int: ...
     @property
     def num_segments(self) -> int: ...
+    def hexpattern(self) -> str: ...
+    def iota(self) -> str: ...
+    def give_command(self, item: GiveItem = GiveItem.Focus) -> str: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
+
+class ApproximatedNumber:
+    @property
+    def numerator(self) -> int: ...
+    @property
+    def denominator(self) -> int: ...
+    @property
+    def number(self) -> GeneratedNumber: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
 
 class BeamOptions:
     bounds: Bounds
     carryover: int
     def __init__(self, bounds: Bounds, carryover: int) -> None: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
 
 class BeamPoolOptions:
     bounds: Bounds
     carryover: int
     num_threads: int
     def __init__(self, bounds: Bounds, carryover: int, num_threads: int) -> None: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
 
 class BeamSplitOptions:
     bounds: Bounds
     carryover: int
     num_threads: int
     def __init__(self, bounds: Bounds, carryover: int, num_threads: int) -> None: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
 
 class AStarOptions:
     def __init__(self) -> None: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
 
 class AStarSplitOptions:
     num_threads: int
     def __init__(self, num_threads: int) -> None: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
+
+class NamedOptions:
+    @property
+    def name(self) -> str: ...
+    @property
+    def params(self) -> dict[str, object]: ...
+    def __init__(self, name: str, params: dict[str, object] | None = None) -> None: ...
+    def to_json(self) -> str: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
+
+Options = BeamOptions | BeamPoolOptions | BeamSplitOptions | AStarOptions | AStarSplitOptions | NamedOptions
+
+# strings are parsed with parse_expression, eg. "2^20+3" or "-0x1F/4"
+# floats are converted exactly, so they need a numerator below 2^63 and a denominator of at most 2^62
+# the searches can't reach long binary expansions like 0.1's denominator of 2^55, only doubles like 0.375
+Ratio = int | float | tuple[int, int] | str
+
+class MultipleCeiling(TypedDict):
+    multiple: Ratio
+
+class AbsoluteCeiling(TypedDict):
+    absolute: Ratio
+
+# True discards paths larger than the target, False keeps them
+Ceiling = bool | MultipleCeiling | AbsoluteCeiling
 
-Options = BeamOptions | BeamPoolOptions | BeamSplitOptions | AStarOptions | AStarSplitOptions
+class GenerationRequest:
+    def __init__(
+        self,
+        target: Ratio,
+        algorithm: Options,
+        ceiling: Ceiling | None = None,
+        fractions: bool | FractionPolicy | None = None,
+        objective: Objective | None = None,
+    ) -> None: ...
+    @classmethod
+    def from_json(cls, json: str) -> Self: ...
+    @classmethod
+    def from_toml(cls, toml: str) -> Self: ...
+    def to_json(self) -> str: ...
+    def to_toml(self) -> str: ...
+    def validate(self) -> None: ...
+    def generate(self) -> GeneratedNumber | None: ...
+
+# reads a table from pregen in any format
+class NumberTable:
+    def __init__(self, path: str | os.PathLike[str]) -> None: ...
+    def get(self, target: Ratio) -> GeneratedNumber | None: ...
+    def __contains__(self, target: Ratio) -> bool: ...
+    def __len__(self) -> int: ...
 
 def generate_number_pattern(
-    target: int | tuple[int, int],
-    trim_larger: bool,
-    allow_fractions: bool,
+    target: Ratio,
+    ceiling: Ceiling,
+    allow_fractions: bool | FractionPolicy,
     options: Options,
 ) -> GeneratedNumber | None: ...
+
+# fractions defaults to allowing every fraction
+def approximate_number_pattern(
+    value: float,
+    tolerance: float,
+    ceiling: Ceiling,
+    options: Options,
+    fractions: bool | FractionPolicy | None = None,
+    objective: Objective | None = None,
+) -> ApproximatedNumber | None: ...
+
+# a target's result from generate_many, with either a number or an error if it failed
+class GenerationResult:
+    @property
+    def index(self) -> int: ...
+    @property
+    def target(self) -> object: ...
+    @property
+    def number(self) -> GeneratedNumber | None: ...
+    @property
+    def error(self) -> str | None: ...
+    # seconds spent generating the target
+    @property
+    def time(self) -> float: ...
+
+class GenerationResults(Iterator[GenerationResult]):
+    def __iter__(self) -> Self: ...
+    def __next__(self) -> GenerationResult: ...
+
+@overload
+def generate_many(
+    targets: Iterable[Ratio],
+    options: Options,
+    ceiling: Ceiling | None = None,
+    fractions: bool | FractionPolicy | None = None,
+    objective: Objective | None = None,
+    max_workers: int | None = None,
+    as_completed: Literal[False] = False,
+) -> list[GenerationResult]: ...
+@overload
+def generate_many(
+    targets: Iterable[Ratio],
+    options: Options,
+    ceiling: Ceiling | None = None,
+    fractions: bool | FractionPolicy | None = None,
+    objective: Objective | None = None,
+    max_workers: int | None = None,
+    *,
+    as_completed: Literal[True],
+) -> GenerationResults: ...
+
+def generator_names() -> list[str]: ...
+
+def parse_expression(expression: str) -> tuple[int, int]: ...
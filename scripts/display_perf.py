@@ -4,7 +4,7 @@ import matplotlib.pyplot as plt
 import numpy as np
 import pandas as pd
 from matplotlib.axes import Axes
-from measure_perf import PerfDump
+from perf_dump import PerfDump
 
 DfDump = tuple[str, pd.DataFrame]
 
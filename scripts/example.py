@@ -1,3 +1,7 @@
-from hexnumgen import BeamOptions, Bounds, generate_number_pattern
+from hexnumgen import BeamOptions, Bounds, generate_many, generate_number_pattern
 
 print(generate_number_pattern(100, False, False, BeamOptions(Bounds(8, 8, 8), 25)))
+
+# generates the targets in parallel, one thread per CPU by default
+for result in generate_many(range(95, 105), BeamOptions(Bounds(8, 8, 8), 25), ceiling=False):
+    print(result.target, result.number or result.error)
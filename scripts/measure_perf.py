@@ -2,10 +2,10 @@ from __future__ import annotations
 
 import json
 from pathlib import Path
-from timeit import default_timer as timer
-from typing import TYPE_CHECKING, Required, TypedDict
+from typing import TYPE_CHECKING
 
-from tqdm import tqdm, trange
+from perf_dump import PerfDump, PerfDumpItem
+from tqdm import tqdm
 
 from hexnumgen import (
     AStarOptions,
@@ -14,50 +14,19 @@ from hexnumgen import (
     BeamPoolOptions,
     BeamSplitOptions,
     Bounds,
-    generate_number_pattern,
+    generate_many,
 )
 
 if TYPE_CHECKING:
     from hexnumgen import Options
 
 
-class PerfDumpItem(TypedDict, total=False):
-    target: Required[int]
-    time: Required[float]
-    pattern: str
-    points: int
-    segments: int
-    bounds: tuple[int, int, int]
-    largest_dim: int
-    quasi_area: int
-
-
-class PerfDump(TypedDict):
-    algorithm: str
-    carryover: int | None
-    num_threads: int | None
-    data: list[PerfDumpItem]
-
-
 def create_dump(options: Options) -> PerfDump:
-    match options:
-        case BeamOptions():
-            carryover = options.carryover
-            num_threads = None
-        case BeamPoolOptions() | BeamSplitOptions():
-            carryover = options.carryover
-            num_threads = options.num_threads
-        case AStarOptions():
-            carryover = None
-            num_threads = None
-        case AStarSplitOptions():
-            carryover = None
-            num_threads = options.num_threads
-
+    params = json.loads(options.to_json())
     return PerfDump(
         algorithm=type(options).__name__.replace("Options", ""),
-        carryover=carryover,
-        num_threads=num_threads,
+        carryover=params.get("carryover"),
+        num_threads=params.get("num_threads"),
         data=[],
     )
 
@@ -73,24 +42,26 @@ def get_dump_filename(dump: PerfDump, trim_larger: bool) -> str:
     return filename + ".json"
 
 
-def measure(out_dir: Path, trim_larger: bool, options: Options):
+def measure(out_dir: Path, trim_larger: bool, options: Options, max_workers: int | None):
     dump = create_dump(options)
-
     filename = get_dump_filename(dump, trim_larger)
-    for n in trange(1001, desc=filename):
-        start = timer()
-        number = generate_number_pattern(
-            target=n,
-            trim_larger=trim_larger,
-            allow_fractions=False,
-            options=options,
-        )
-        time = timer() - start
-
-        item = PerfDumpItem(target=n, time=time)
-        if number is None:
-            tqdm.write(f"WARNING: Failed to generate {n}")
+
+    # targets run in parallel, so their times are a bit slower than running them one at a time
+    targets = range(1001)
+    results = generate_many(
+        targets,
+        options,
+        ceiling=trim_larger,
+        fractions=False,
+        max_workers=max_workers,
+        as_completed=True,
+    )
+    for result in tqdm(results, total=len(targets), desc=filename):
+        item = PerfDumpItem(target=result.target, time=result.time)
+        if result.number is None:
+            tqdm.write(f"WARNING: Failed to generate {result.target}: {result.error or 'no pattern found'}")
         else:
+            number = result.number
             bounds = number.bounds
             item["pattern"] = number.pattern
             item["points"] = number.num_points
@@ -99,6 +70,7 @@ def measure(out_dir: Path, trim_larger: bool, options: Options):
             item["bounds"] = (bounds.q, bounds.r, bounds.s)
             item["quasi_area"] = bounds.quasi_area
         dump["data"].append(item)
+    dump["data"].sort(key=lambda item: item["target"])
 
     out_path = out_dir / filename
     with out_path.open("w") as f:
@@ -126,7 +98,9 @@ if __name__ == "__main__":
     #     optionses.append(AStarSplitOptions(num_threads))
 
     # actually generate the patterns
+    # None uses every CPU, and 1 generates one target at a time for the most accurate times
+    max_workers = None
     trim_larger = True
     (out_dir := Path("out")).mkdir(exist_ok=True)
     for options in tqdm(optionses, desc="Total"):
-        measure(out_dir, trim_larger, options)
+        measure(out_dir, trim_larger, options, max_workers)
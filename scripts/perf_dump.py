@@ -0,0 +1,20 @@
+from typing import Required, TypedDict
+
+
+# written by `hexnumgen_cli bench`, see src/bin/hexnumgen_cli/perf.rs
+class PerfDumpItem(TypedDict, total=False):
+    target: Required[int]
+    time: Required[float]
+    pattern: str
+    points: int
+    segments: int
+    bounds: tuple[int, int, int]
+    largest_dim: int
+    quasi_area: int
+
+
+class PerfDump(TypedDict):
+    algorithm: str
+    carryover: int | None
+    num_threads: int | None
+    data: list[PerfDumpItem]
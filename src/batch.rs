@@ -0,0 +1,362 @@
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use num_rational::Ratio;
+use parking_lot::Mutex;
+use pyo3::{prelude::*, types::PyList};
+
+use crate::{
+    errors::{HexError, HexResult},
+    numgen::{Objective, SearchHooks},
+    signals, GeneratedNumber, GenerationRequest, GeneratorOptions, PyCeiling, PyFractionPolicy, PyRatio,
+};
+
+/// Runs `f` on every item using up to `num_workers` threads. Workers pull from a shared queue, so one slow item doesn't
+/// hold up the rest of the batch.
+///
+/// `on_result` is called on the calling thread with each item's index and result, in the order they finish. Wrap it
+/// with [`in_order`] to get them in the order of `items` instead.
+pub fn for_each_parallel<T, R, F, C>(items: Vec<T>, num_workers: usize, f: F, mut on_result: C)
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+    C: FnMut(usize, R),
+{
+    let num_workers = num_workers.clamp(1, items.len().max(1));
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let (res_tx, res_rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..num_workers {
+            let res_tx = res_tx.clone();
+            let (queue, f) = (&queue, &f);
+            scope.spawn(move || {
+                loop {
+                    // let-else drops the lock before running f, unlike while-let
+                    let Some((index, item)) = queue.lock().next() else { break };
+                    if res_tx.send((index, f(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        // the receiver finishes once every worker has dropped its sender
+        drop(res_tx);
+        for (index, result) in res_rx {
+            on_result(index, result);
+        }
+    });
+}
+
+/// Adapts a callback for [`for_each_parallel`] so it's called in index order, holding back results that finish early.
+pub fn in_order<R>(mut on_result: impl FnMut(usize, R)) -> impl FnMut(usize, R) {
+    let mut pending = BTreeMap::new();
+    let mut next_index = 0;
+    move |index, result| {
+        pending.insert(index, result);
+        while let Some(result) = pending.remove(&next_index) {
+            on_result(next_index, result);
+            next_index += 1;
+        }
+    }
+}
+
+/// Generates every request in parallel, without running more than `thread_budget` threads at once. Requests using a
+/// multithreaded algorithm count as several threads each.
+pub fn generate_batch<C>(requests: Vec<GenerationRequest>, thread_budget: usize, on_result: C)
+where
+    C: FnMut(usize, HexResult<Option<GeneratedNumber>>),
+{
+    generate_batch_with_hooks(requests, thread_budget, SearchHooks::default(), on_result);
+}
+
+/// Like [`generate_batch`], but passes `hooks` to every search, eg. to cancel the whole batch.
+pub fn generate_batch_with_hooks<C>(
+    requests: Vec<GenerationRequest>,
+    thread_budget: usize,
+    hooks: SearchHooks,
+    on_result: C,
+) where
+    C: FnMut(usize, HexResult<Option<GeneratedNumber>>),
+{
+    let num_workers = batch_workers(&requests, thread_budget);
+    let generate = |request: GenerationRequest| request.generate_with_hooks(hooks.clone());
+    for_each_parallel(requests, num_workers, generate, on_result);
+}
+
+/// How many requests can run at once without going over `thread_budget`.
+fn batch_workers(requests: &[GenerationRequest], thread_budget: usize) -> usize {
+    let threads_per_request = requests.iter().map(|request| request.algorithm.num_threads()).max().unwrap_or(1);
+    thread_budget / threads_per_request.max(1)
+}
+
+/// Like [`generate_batch_with_hooks`], but also passes how long each request took.
+fn generate_batch_timed<C>(requests: Vec<GenerationRequest>, thread_budget: usize, hooks: SearchHooks, on_result: C)
+where
+    C: FnMut(usize, (HexResult<Option<GeneratedNumber>>, Duration)),
+{
+    let num_workers = batch_workers(&requests, thread_budget);
+    let generate = |request: GenerationRequest| {
+        let start = Instant::now();
+        let result = request.generate_with_hooks(hooks.clone());
+        (result, start.elapsed())
+    };
+    for_each_parallel(requests, num_workers, generate, on_result);
+}
+
+/// Checks `max_workers` for `generate_many`, defaulting to the number of CPUs.
+fn max_workers_or_default(max_workers: Option<usize>) -> HexResult<usize> {
+    match max_workers {
+        Some(0) => Err(HexError::InvalidConfig("max_workers must be at least 1".into())),
+        Some(max_workers) => Ok(max_workers),
+        None => Ok(thread::available_parallelism().map_or(1, NonZeroUsize::get)),
+    }
+}
+
+/// A target's pattern or why it failed, and how long it took to generate.
+type Outcome = (Result<Option<GeneratedNumber>, String>, Duration);
+
+/// The result of one target from `generate_many`.
+#[pyclass(module = "hexnumgen", get_all)]
+pub struct GenerationResult {
+    /// Position of the target in the targets passed to `generate_many`
+    pub index: usize,
+    /// The target as it was passed in
+    pub target: PyObject,
+    /// The pattern, or `None` if the search found nothing or the target failed
+    pub number: Option<GeneratedNumber>,
+    /// Why the target failed, eg. because it isn't a valid number
+    pub error: Option<String>,
+    /// Seconds spent generating the target, or 0 if it wasn't a valid number
+    pub time: f64,
+}
+
+impl GenerationResult {
+    fn new(index: usize, target: PyObject, (result, time): Outcome) -> Self {
+        let (number, error) = match result {
+            Ok(number) => (number, None),
+            Err(error) => (None, Some(error)),
+        };
+        Self { index, target, number, error, time: time.as_secs_f64() }
+    }
+}
+
+#[pymethods]
+impl GenerationResult {
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        let number = self.number.as_ref().map_or("None".into(), |number| format!("'{}'", number.__str__()));
+        let error = self.error.as_ref().map_or("None".into(), |error| format!("{error:?}"));
+        Ok(format!(
+            "GenerationResult(index={}, target={}, number={number}, error={error}, time={:?})",
+            self.index,
+            self.target.as_ref(py).repr()?,
+            self.time
+        ))
+    }
+}
+
+/// Yields the results of `generate_many` as each target finishes. Interrupting it or dropping it cancels the targets
+/// that haven't finished yet, since their results would be incomplete.
+#[pyclass(module = "hexnumgen")]
+pub struct GenerationResults {
+    targets: Vec<PyObject>,
+    receiver: Mutex<Receiver<(usize, Outcome)>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[pymethods]
+impl GenerationResults {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python) -> PyResult<Option<GenerationResult>> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        Ok(signals::recv(py, &self.receiver, &self.cancelled)?
+            .map(|(index, outcome)| GenerationResult::new(index, self.targets[index].clone_ref(py), outcome)))
+    }
+}
+
+impl Drop for GenerationResults {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Generates every target in parallel, using up to `max_workers` threads (defaults to the number of CPUs, and
+/// multithreaded algorithms count as several). Raises `ValueError` if `max_workers` is 0. Returns a list of results in
+/// the same order as `targets`, or an iterator that yields them as they finish with `as_completed`. A target that fails
+/// gets an error in its result instead of raising, so it doesn't lose the rest.
+#[pyfunction]
+#[pyo3(signature = (targets, options, ceiling=None, fractions=None, objective=None, max_workers=None, as_completed=false))]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_many(
+    py: Python,
+    targets: &PyAny,
+    options: GeneratorOptions,
+    ceiling: Option<PyCeiling>,
+    fractions: Option<PyFractionPolicy>,
+    objective: Option<Objective>,
+    max_workers: Option<usize>,
+    as_completed: bool,
+) -> PyResult<PyObject> {
+    options.validate()?;
+    let max_workers = max_workers_or_default(max_workers)?;
+    let template = GenerationRequest {
+        target: 0.into(),
+        objective: objective.unwrap_or_default(),
+        ceiling: ceiling.map(TryInto::try_into).transpose()?.unwrap_or_default(),
+        fractions: fractions.map(Into::into).unwrap_or_default(),
+        algorithm: options,
+    };
+
+    // targets that aren't numbers fail straight away, without stopping the rest
+    let (mut objects, mut requests, mut indices, mut invalid) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (index, target) in targets.iter()?.enumerate() {
+        let target = target?;
+        let parsed = match target.extract::<PyRatio>() {
+            Ok(ratio) => Ok(ratio),
+            Err(_) => Err(format!("expected a number, got {}", target.get_type().name()?)),
+        };
+        match parsed.and_then(|ratio| Ratio::try_from(ratio).map_err(|err| err.to_string())) {
+            Ok(target) => {
+                requests.push(GenerationRequest { target, ..template.clone() });
+                indices.push(index);
+            }
+            Err(error) => invalid.push((index, error)),
+        }
+        objects.push(target.into_py(py));
+    }
+
+    if as_completed {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let hooks = SearchHooks::default().cancel_on(cancelled.clone());
+        let (sender, receiver) = mpsc::channel();
+        for (index, error) in invalid {
+            sender.send((index, (Err(error), Duration::ZERO))).ok();
+        }
+        // sending fails once the iterator is dropped, which also cancels the remaining searches
+        thread::spawn(move || {
+            generate_batch_timed(requests, max_workers, hooks, |i, (result, time)| {
+                sender.send((indices[i], (result.map_err(|err| err.to_string()), time))).ok();
+            })
+        });
+        let results = GenerationResults { targets: objects, receiver: Mutex::new(receiver), cancelled };
+        return Ok(results.into_py(py));
+    }
+
+    let mut outcomes: Vec<Option<Outcome>> = (0..objects.len()).map(|_| None).collect();
+    for (index, error) in invalid {
+        outcomes[index] = Some((Err(error), Duration::ZERO));
+    }
+    signals::allow_threads(py, |hooks| {
+        generate_batch_timed(requests, max_workers, hooks, |i, (result, time)| {
+            outcomes[indices[i]] = Some((result.map_err(|err| err.to_string()), time));
+        })
+    })?;
+
+    let results = objects.into_iter().zip(outcomes).enumerate().map(|(index, (target, outcome))| {
+        GenerationResult::new(index, target, outcome.expect("every target has a result")).into_py(py)
+    });
+    Ok(PyList::new(py, results).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AStarOptions;
+
+    #[test]
+    fn for_each_parallel_runs_every_item() {
+        let mut results = Vec::new();
+        for_each_parallel((0..50).collect(), 4, |x: u32| x * 2, |index, result| results.push((index, result)));
+        results.sort();
+        assert_eq!(results, (0..50).map(|x| (x as usize, x * 2)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn for_each_parallel_uses_at_least_one_worker() {
+        let mut count = 0;
+        for_each_parallel(vec![1, 2, 3], 0, |x: i32| x, |_, _| count += 1);
+        assert_eq!(count, 3);
+
+        for_each_parallel(Vec::<i32>::new(), 8, |x| x, |_, _: i32| panic!("no items"));
+    }
+
+    #[test]
+    fn in_order_holds_back_early_results() {
+        let mut seen = Vec::new();
+        {
+            let mut callback = in_order(|index, result: char| seen.push((index, result)));
+            callback(2, 'c');
+            callback(0, 'a');
+            callback(3, 'd');
+            callback(1, 'b');
+        }
+        assert_eq!(seen, [(0, 'a'), (1, 'b'), (2, 'c'), (3, 'd')]);
+    }
+
+    #[test]
+    fn generate_batch_reports_each_request() {
+        let request = |target: i64| GenerationRequest {
+            target: target.into(),
+            objective: Objective::default(),
+            ceiling: Default::default(),
+            fractions: Default::default(),
+            algorithm: GeneratorOptions::AStar(AStarOptions {}),
+        };
+        let mut results = BTreeMap::new();
+        generate_batch(vec![request(5), request(-12), request(100)], 2, |index, result| {
+            results.insert(index, result.unwrap().unwrap());
+        });
+        assert_eq!(results.len(), 3);
+        assert!(results[&0].pattern.starts_with("aqaa"));
+        assert!(results[&1].pattern.starts_with("dedd"));
+    }
+
+    #[test]
+    fn generate_batch_timed_reports_each_time() {
+        let requests = vec![
+            GenerationRequest::new(5.into(), GeneratorOptions::AStar(AStarOptions {})),
+            GenerationRequest::new(Ratio::new(1, 3), GeneratorOptions::AStar(AStarOptions {})),
+        ];
+        let mut results = BTreeMap::new();
+        generate_batch_timed(requests, 2, SearchHooks::default(), |index, (result, time)| {
+            results.insert(index, (result.is_ok(), time));
+        });
+        assert!(results[&0].0);
+        assert!(!results[&1].0);
+        assert!(results[&0].1 > Duration::ZERO);
+    }
+
+    #[test]
+    fn max_workers_must_be_positive() {
+        assert!(matches!(max_workers_or_default(Some(0)), Err(HexError::InvalidConfig(_))));
+        assert_eq!(max_workers_or_default(Some(3)).unwrap(), 3);
+        assert!(max_workers_or_default(None).unwrap() >= 1);
+    }
+
+    #[test]
+    fn batch_workers_count_multithreaded_algorithms() {
+        let request = |num_threads: usize| {
+            let options = serde_json::json!({ "name": "astar-split", "num_threads": num_threads });
+            GenerationRequest::new(1.into(), serde_json::from_value(options).unwrap())
+        };
+        assert_eq!(batch_workers(&[request(2), request(4)], 8), 2);
+        assert_eq!(batch_workers(&[GenerationRequest::new(1.into(), GeneratorOptions::AStar(AStarOptions {}))], 8), 8);
+        assert_eq!(batch_workers(&[], 3), 3);
+    }
+}
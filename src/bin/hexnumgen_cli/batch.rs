@@ -0,0 +1,109 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+    thread,
+    time::Instant,
+};
+
+use clap::Args;
+use hexnumgen::{for_each_parallel, in_order, GenerationRequest, GeneratorOptions};
+use serde::Serialize;
+
+use crate::{output::Record, ratio::expand_range, Cli};
+
+#[derive(Args)]
+pub struct BatchArgs {
+    /// File to read targets from, one per line, eg. `10`, `1.25`, `5/4` or `100..200` (reads stdin if omitted or `-`)
+    input: Option<PathBuf>,
+
+    /// Maximum number of threads to use at once, including the algorithm's own threads (defaults to the number of CPUs)
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    #[command(subcommand)]
+    options: Option<GeneratorOptions>,
+}
+
+/// One line of batch output: either a result, or the input that failed and why.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Line {
+    Ok(Record),
+    Err { input: String, error: String },
+}
+
+/// One target to generate, or a line of input that couldn't be parsed.
+type Job = (String, Result<GenerationRequest, String>);
+
+pub fn run(cli: &Cli, args: &BatchArgs) -> Result<(), String> {
+    if cli.target.is_some() {
+        return Err("batch reads its targets from the input, so don't pass a target before it".into());
+    }
+    if cli.tolerance.is_some() {
+        return Err("--tolerance isn't supported in batch mode".into());
+    }
+
+    let input = match &args.input {
+        Some(path) if path.as_os_str() != "-" => {
+            fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?
+        }
+        _ => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).map_err(|err| format!("Failed to read stdin: {err}"))?;
+            input
+        }
+    };
+
+    // the target is replaced for each job, but build this first to fail early if the config or algorithm is missing
+    let template = cli.request(Some(0.into()), args.options.as_ref())?;
+
+    let job = |input: String| {
+        let request = cli.parse_target(&input).map(|target| GenerationRequest { target, ..template.clone() });
+        (input, request)
+    };
+
+    let mut jobs: Vec<Job> = Vec::new();
+    for line in input.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        match expand_range(line) {
+            Ok(Some(targets)) => jobs.extend(targets.map(|target| job(target.to_string()))),
+            Ok(None) => jobs.push(job(line.to_string())),
+            Err(err) => jobs.push((line.to_string(), Err(err))),
+        }
+    }
+
+    let total = jobs.len();
+    let threads = args.threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, Into::into));
+    let num_workers = threads / template.algorithm.num_threads().max(1);
+
+    let mut failures = 0;
+    for_each_parallel(
+        jobs,
+        num_workers,
+        |(input, request)| request.and_then(generate).map_err(|error| (input, error)),
+        in_order(|_, result: Result<Record, (String, String)>| {
+            let line = match result {
+                Ok(record) => Line::Ok(record),
+                Err((input, error)) => {
+                    failures += 1;
+                    Line::Err { input, error }
+                }
+            };
+            println!("{}", serde_json::to_string(&line).unwrap());
+        }),
+    );
+
+    match failures {
+        0 => Ok(()),
+        _ => Err(format!("{failures} of {total} targets failed")),
+    }
+}
+
+fn generate(request: GenerationRequest) -> Result<Record, String> {
+    let start = Instant::now();
+    let result = request.generate().map_err(|err| err.to_string())?;
+    let time = start.elapsed().as_secs_f64();
+
+    let number = result.ok_or_else(|| format!("No pattern found for {}", request.target))?;
+    Record::new(request.target, request.target, number, &request.algorithm, time)
+}
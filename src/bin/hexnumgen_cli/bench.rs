@@ -0,0 +1,224 @@
+use std::{fs, path::PathBuf, time::Instant};
+
+use clap::{Args, ValueEnum};
+use hexnumgen::{
+    AStarOptions, AStarSplitOptions, BeamOptions, BeamPoolOptions, BeamSplitOptions, Bounds, Ceiling,
+    GenerationRequest, GeneratorOptions,
+};
+use num_rational::Ratio;
+
+use crate::{
+    perf::{PerfDump, PerfDumpItem},
+    ratio::expand_range,
+    Cli,
+};
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Algorithms to benchmark
+    #[arg(required = true, value_enum)]
+    algorithms: Vec<Algorithm>,
+
+    /// Range of targets to generate, eg. `0..=1000` or `100..200`
+    #[arg(long, default_value = "0..=1000")]
+    targets: String,
+
+    /// Carryovers to benchmark the beam search algorithms with, eg. `50,100,200`
+    #[arg(short, long, value_delimiter = ',', default_value = "25")]
+    carryovers: Vec<usize>,
+
+    /// Thread counts to benchmark the parallel algorithms with, eg. `2,4,6,8`
+    #[arg(short = 't', long = "threads", value_delimiter = ',', default_value = "2")]
+    num_threads: Vec<usize>,
+
+    #[command(flatten)]
+    bounds: Bounds,
+
+    /// Number of times to generate each target, recording the median time
+    #[arg(long, default_value_t = 1)]
+    trials: usize,
+
+    /// Directory to write the performance dumps to, one file per configuration
+    #[arg(short, long, default_value = "out")]
+    out_dir: PathBuf,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Algorithm {
+    Beam,
+    BeamPool,
+    BeamSplit,
+    #[value(name = "astar")]
+    AStar,
+    #[value(name = "astar-split")]
+    AStarSplit,
+}
+
+impl BenchArgs {
+    /// Every combination of algorithm, carryover and thread count that applies to each algorithm.
+    fn configurations(&self) -> Vec<GeneratorOptions> {
+        let bounds = self.bounds;
+        let mut configurations = Vec::new();
+        for algorithm in &self.algorithms {
+            match algorithm {
+                Algorithm::Beam => configurations.extend(
+                    self.carryovers.iter().map(|&carryover| GeneratorOptions::Beam(BeamOptions { bounds, carryover })),
+                ),
+                Algorithm::BeamPool | Algorithm::BeamSplit => {
+                    for &carryover in &self.carryovers {
+                        for &num_threads in &self.num_threads {
+                            configurations.push(match algorithm {
+                                Algorithm::BeamPool => {
+                                    GeneratorOptions::BeamPool(BeamPoolOptions { bounds, carryover, num_threads })
+                                }
+                                _ => GeneratorOptions::BeamSplit(BeamSplitOptions { bounds, carryover, num_threads }),
+                            });
+                        }
+                    }
+                }
+                Algorithm::AStar => configurations.push(GeneratorOptions::AStar(AStarOptions {})),
+                Algorithm::AStarSplit => configurations.extend(
+                    self.num_threads
+                        .iter()
+                        .map(|&num_threads| GeneratorOptions::AStarSplit(AStarSplitOptions { num_threads })),
+                ),
+            }
+        }
+        configurations
+    }
+}
+
+pub fn run(cli: &Cli, args: &BenchArgs) -> Result<(), String> {
+    if cli.target.is_some() {
+        return Err("bench generates the targets given by --targets, so don't pass a target before it".into());
+    }
+    if cli.tolerance.is_some() {
+        return Err("--tolerance isn't supported by bench".into());
+    }
+    if args.trials == 0 {
+        return Err("--trials must be at least 1".into());
+    }
+
+    let targets: Vec<i64> =
+        expand_range(&args.targets)?.ok_or_else(|| format!("Invalid range: {}", args.targets))?.collect();
+    fs::create_dir_all(&args.out_dir).map_err(|err| format!("Failed to create {}: {err}", args.out_dir.display()))?;
+
+    let configurations = args.configurations();
+    for (index, options) in configurations.iter().enumerate() {
+        let template = cli.request(Some(0.into()), Some(options))?;
+        template.algorithm.validate().map_err(|err| err.to_string())?;
+
+        let mut dump = PerfDump::new(options);
+        let filename = dump.filename(template.ceiling != Ceiling::Unbounded);
+        eprintln!("[{}/{}] {filename}", index + 1, configurations.len());
+
+        for &target in &targets {
+            let target = if cli.negative { -target } else { target };
+            let request = GenerationRequest { target: Ratio::from_integer(target), ..template.clone() };
+            dump.data.push(measure(&request, target, args.trials));
+        }
+
+        dump.write(&args.out_dir.join(filename))?;
+    }
+    Ok(())
+}
+
+/// Generates `target` `trials` times, keeping the last result and the median time. Failures are recorded with only the
+/// target and time, so one bad target doesn't lose the rest of the run.
+fn measure(request: &GenerationRequest, target: i64, trials: usize) -> PerfDumpItem {
+    let mut times = Vec::with_capacity(trials);
+    let mut result = Ok(None);
+    for _ in 0..trials {
+        let start = Instant::now();
+        result = request.generate();
+        times.push(start.elapsed().as_secs_f64());
+    }
+
+    let number = match result {
+        Ok(Some(number)) => Some(number),
+        Ok(None) => {
+            eprintln!("WARNING: Failed to generate {target}");
+            None
+        }
+        Err(err) => {
+            eprintln!("WARNING: Failed to generate {target}: {err}");
+            None
+        }
+    };
+    PerfDumpItem::new(target, median(times), number)
+}
+
+fn median(mut times: Vec<f64>) -> f64 {
+    times.sort_by(f64::total_cmp);
+    let middle = times.len() / 2;
+    if times.len() % 2 == 0 {
+        (times[middle - 1] + times[middle]) / 2.0
+    } else {
+        times[middle]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Wrapper {
+        #[command(flatten)]
+        args: BenchArgs,
+    }
+
+    fn filenames(args: &[&str]) -> Vec<String> {
+        let args = Wrapper::parse_from(["bench"].iter().chain(args)).args;
+        args.configurations().iter().map(|options| PerfDump::new(options).filename(true)).collect()
+    }
+
+    #[test]
+    fn configurations_cover_every_combination() {
+        assert_eq!(
+            filenames(&["beam", "beam-split", "astar", "astar-split", "-c", "10,20", "-t", "2,4"]),
+            [
+                "Beam_c10.json",
+                "Beam_c20.json",
+                "BeamSplit_c10_t2.json",
+                "BeamSplit_c10_t4.json",
+                "BeamSplit_c20_t2.json",
+                "BeamSplit_c20_t4.json",
+                "AStar.json",
+                "AStarSplit_t2.json",
+                "AStarSplit_t4.json",
+            ]
+        );
+        assert_eq!(filenames(&["beam-pool"]), ["BeamPool_c25_t2.json"]);
+    }
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(vec![4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_eq!(median(vec![5.0]), 5.0);
+    }
+
+    #[test]
+    fn measure_records_results_and_failures() {
+        let request = |target: i64, ceiling| GenerationRequest {
+            target: Ratio::from_integer(target),
+            objective: Default::default(),
+            ceiling,
+            fractions: Default::default(),
+            algorithm: GeneratorOptions::AStar(AStarOptions {}),
+        };
+
+        let item = measure(&request(10, Ceiling::default()), 10, 3);
+        assert_eq!(item.target, 10);
+        assert!(item.pattern.as_ref().is_some_and(|pattern| pattern.starts_with("aqaa")));
+        assert!(!item.failed());
+
+        // a ceiling below the target makes generating fail, which is recorded instead of aborting
+        let item = measure(&request(10, Ceiling::Absolute(5.into())), 10, 1);
+        assert!(item.failed());
+        assert!(item.pattern.is_none());
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use clap::Args;
+use hexnumgen::Objective;
+
+use crate::{
+    perf::{PerfDump, PerfDumpItem},
+    Cli,
+};
+
+#[derive(Args)]
+pub struct CompareArgs {
+    /// Performance dump to compare against, eg. from before a change
+    old: PathBuf,
+
+    /// Performance dump to compare, eg. from after a change
+    new: PathBuf,
+
+    /// Print every target, instead of only the ones that got better or worse
+    #[arg(long)]
+    all: bool,
+
+    /// Exit with an error if more than this many targets got worse, including targets that started failing
+    #[arg(long)]
+    max_regressions: Option<usize>,
+
+    /// Exit with an error if the total time increased by more than this percentage
+    #[arg(long)]
+    max_time_increase: Option<f64>,
+
+    /// Exit with an error if the total segments increased by more than this percentage
+    #[arg(long)]
+    max_segments_increase: Option<f64>,
+
+    /// Exit with an error if the total quasi-area increased by more than this percentage
+    #[arg(long)]
+    max_quasi_area_increase: Option<f64>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Better,
+    Worse,
+    Unchanged,
+    StartedFailing,
+    StoppedFailing,
+    StillFailing,
+    /// Both have a pattern, but the dumps don't have the metric the objective needs
+    Unavailable,
+}
+
+impl Change {
+    fn between(old: &PerfDumpItem, new: &PerfDumpItem, objective: Objective) -> Self {
+        match (old.failed(), new.failed()) {
+            (false, true) => return Self::StartedFailing,
+            (true, false) => return Self::StoppedFailing,
+            (true, true) => return Self::StillFailing,
+            (false, false) => {}
+        }
+        match (old.key(objective), new.key(objective)) {
+            (Some(old_key), Some(new_key)) if new_key < old_key => Self::Better,
+            (Some(old_key), Some(new_key)) if new_key > old_key => Self::Worse,
+            (Some(_), Some(_)) => Self::Unchanged,
+            _ => Self::Unavailable,
+        }
+    }
+
+    fn is_regression(self) -> bool {
+        matches!(self, Self::Worse | Self::StartedFailing)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Better => "better",
+            Self::Worse => "WORSE",
+            Self::Unchanged => "unchanged",
+            Self::StartedFailing => "STARTED FAILING",
+            Self::StoppedFailing => "stopped failing",
+            Self::StillFailing => "still failing",
+            Self::Unavailable => "metric unavailable",
+        }
+    }
+}
+
+/// Sums of each metric over the targets that succeeded in both dumps, so they're comparable.
+#[derive(Default)]
+struct Totals {
+    time: (f64, f64),
+    segments: (usize, usize),
+    quasi_area: (usize, usize),
+}
+
+pub fn run(cli: &Cli, args: &CompareArgs) -> Result<(), String> {
+    if cli.target.is_some() {
+        return Err("compare reads its targets from the dumps, so don't pass a target before it".into());
+    }
+    let objective = cli.limits.objective();
+
+    let old = PerfDump::read(&args.old)?;
+    let new = PerfDump::read(&args.new)?;
+    let old_items: BTreeMap<_, _> = old.data.iter().map(|item| (item.target, item)).collect();
+    let new_items: BTreeMap<_, _> = new.data.iter().map(|item| (item.target, item)).collect();
+    let targets: BTreeSet<_> = old_items.keys().chain(new_items.keys()).copied().collect();
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut started_failing = Vec::new();
+    let mut stopped_failing = Vec::new();
+    let mut only_old = 0;
+    let mut only_new = 0;
+    let mut totals = Totals::default();
+
+    for target in targets {
+        let (old_item, new_item) = match (old_items.get(&target), new_items.get(&target)) {
+            (Some(old_item), Some(new_item)) => (*old_item, *new_item),
+            (Some(_), None) => {
+                only_old += 1;
+                continue;
+            }
+            _ => {
+                only_new += 1;
+                continue;
+            }
+        };
+
+        let change = Change::between(old_item, new_item, objective);
+        *counts.entry(change.label()).or_default() += 1;
+        match change {
+            Change::StartedFailing => started_failing.push(target),
+            Change::StoppedFailing => stopped_failing.push(target),
+            _ => {}
+        }
+
+        if !old_item.failed() && !new_item.failed() {
+            totals.time.0 += old_item.time;
+            totals.time.1 += new_item.time;
+            totals.segments.0 += old_item.segments.unwrap_or_default();
+            totals.segments.1 += new_item.segments.unwrap_or_default();
+            totals.quasi_area.0 += old_item.quasi_area.unwrap_or_default() as usize;
+            totals.quasi_area.1 += new_item.quasi_area.unwrap_or_default() as usize;
+        }
+
+        if args.all || !matches!(change, Change::Unchanged | Change::StillFailing) {
+            print_target(target, old_item, new_item, change);
+        }
+    }
+
+    let regressions = counts.get(Change::Worse.label()).unwrap_or(&0) + started_failing.len();
+
+    println!();
+    println!("Old: {}", args.old.display());
+    println!("New: {}", args.new.display());
+    if only_old + only_new > 0 {
+        println!("Skipped {only_old} targets only in the old dump and {only_new} only in the new dump");
+    }
+    println!(
+        "Time:       {:.3}s -> {:.3}s ({:+.1}%)",
+        totals.time.0,
+        totals.time.1,
+        percent_change(totals.time.0, totals.time.1)
+    );
+    println!(
+        "Segments:   {} -> {} ({:+.1}%)",
+        totals.segments.0,
+        totals.segments.1,
+        percent_change(totals.segments.0 as f64, totals.segments.1 as f64)
+    );
+    println!(
+        "Quasi-area: {} -> {} ({:+.1}%)",
+        totals.quasi_area.0,
+        totals.quasi_area.1,
+        percent_change(totals.quasi_area.0 as f64, totals.quasi_area.1 as f64)
+    );
+    println!(
+        "Targets:    {}",
+        counts.iter().map(|(label, count)| format!("{count} {}", label.to_lowercase())).collect::<Vec<_>>().join(", ")
+    );
+    if !started_failing.is_empty() {
+        println!("Started failing: {}", join(&started_failing));
+    }
+    if !stopped_failing.is_empty() {
+        println!("Stopped failing: {}", join(&stopped_failing));
+    }
+
+    let mut exceeded = Vec::new();
+    if let Some(max) = args.max_regressions.filter(|&max| regressions > max) {
+        exceeded.push(format!("{regressions} targets got worse (max {max})"));
+    }
+    let increases = [
+        ("time", args.max_time_increase, totals.time.0, totals.time.1),
+        ("segments", args.max_segments_increase, totals.segments.0 as f64, totals.segments.1 as f64),
+        ("quasi-area", args.max_quasi_area_increase, totals.quasi_area.0 as f64, totals.quasi_area.1 as f64),
+    ];
+    for (name, max, old_total, new_total) in increases {
+        let change = percent_change(old_total, new_total);
+        if let Some(max) = max.filter(|&max| change > max) {
+            exceeded.push(format!("total {name} increased by {change:.1}% (max {max}%)"));
+        }
+    }
+
+    match exceeded.len() {
+        0 => Ok(()),
+        _ => Err(format!("Thresholds exceeded: {}", exceeded.join("; "))),
+    }
+}
+
+fn print_target(target: i64, old: &PerfDumpItem, new: &PerfDumpItem, change: Change) {
+    let metric = |item: &PerfDumpItem, value: Option<usize>| match value {
+        Some(value) if !item.failed() => value.to_string(),
+        _ => "-".to_string(),
+    };
+    println!(
+        "{} {target:>8}  time {:.3}ms -> {:.3}ms ({:+.1}%)  segments {} -> {}  quasi-area {} -> {}  {}",
+        if change.is_regression() { '!' } else { ' ' },
+        old.time * 1000.0,
+        new.time * 1000.0,
+        percent_change(old.time, new.time),
+        metric(old, old.segments),
+        metric(new, new.segments),
+        metric(old, old.quasi_area.map(|quasi_area| quasi_area as usize)),
+        metric(new, new.quasi_area.map(|quasi_area| quasi_area as usize)),
+        change.label(),
+    );
+}
+
+fn percent_change(old: f64, new: f64) -> f64 {
+    if old == 0.0 {
+        if new == 0.0 {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    } else {
+        (new - old) / old * 100.0
+    }
+}
+
+fn join(targets: &[i64]) -> String {
+    targets.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(segments: usize, quasi_area: u32) -> PerfDumpItem {
+        PerfDumpItem {
+            target: 1,
+            time: 0.0,
+            pattern: None,
+            points: Some(segments + 1),
+            segments: Some(segments),
+            largest_dim: Some(2),
+            bounds: None,
+            quasi_area: Some(quasi_area),
+        }
+    }
+
+    fn failed() -> PerfDumpItem {
+        PerfDumpItem::new(1, 0.0, None)
+    }
+
+    #[test]
+    fn classifies_changes_by_objective() {
+        let (fewer_segments, smaller_area) = (item(5, 30), item(6, 20));
+        assert!(Change::between(&smaller_area, &fewer_segments, Objective::Segments) == Change::Better);
+        assert!(Change::between(&smaller_area, &fewer_segments, Objective::QuasiArea) == Change::Worse);
+        assert!(Change::between(&smaller_area, &item(6, 20), Objective::QuasiArea) == Change::Unchanged);
+    }
+
+    #[test]
+    fn classifies_failures() {
+        let ok = item(5, 30);
+        assert!(Change::between(&ok, &failed(), Objective::Segments) == Change::StartedFailing);
+        assert!(Change::between(&failed(), &ok, Objective::Segments) == Change::StoppedFailing);
+        assert!(Change::between(&failed(), &failed(), Objective::Segments) == Change::StillFailing);
+    }
+
+    #[test]
+    fn missing_metrics_are_not_failures() {
+        let old = PerfDumpItem { points: None, ..item(5, 30) };
+        let change = Change::between(&old, &item(5, 30), Objective::Points);
+        assert!(change == Change::Unavailable);
+        assert!(!change.is_regression());
+
+        // recomputed from the pattern when it's there
+        let old = PerfDumpItem { pattern: Some("aqaaw".into()), ..old };
+        assert!(Change::between(&old, &item(5, 30), Objective::Points) == Change::Worse);
+    }
+
+    #[test]
+    fn regressions() {
+        assert!(Change::Worse.is_regression());
+        assert!(Change::StartedFailing.is_regression());
+        assert!(!Change::StillFailing.is_regression());
+        assert!(!Change::Unavailable.is_regression());
+        assert!(!Change::Better.is_regression());
+    }
+
+    #[test]
+    fn percent_change_handles_zero() {
+        assert_eq!(percent_change(2.0, 3.0), 50.0);
+        assert_eq!(percent_change(4.0, 3.0), -25.0);
+        assert_eq!(percent_change(0.0, 0.0), 0.0);
+        assert_eq!(percent_change(0.0, 1.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn joins_targets() {
+        assert_eq!(join(&[1, -2, 3]), "1, -2, 3");
+        assert_eq!(join(&[]), "");
+    }
+}
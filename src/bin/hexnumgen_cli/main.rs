@@ -0,0 +1,162 @@
+mod batch;
+mod bench;
+mod compare;
+mod output;
+mod perf;
+mod ratio;
+mod repl;
+mod report;
+
+use std::{fs, path::PathBuf, time::Instant};
+
+use batch::BatchArgs;
+use bench::BenchArgs;
+use clap::{Parser, Subcommand};
+use compare::CompareArgs;
+use hexnumgen::{approximate_number_pattern, float_target, Ceiling, GenerationRequest, GeneratorOptions, LimitArgs};
+use num_rational::Ratio;
+use num_traits::ToPrimitive;
+use output::{Format, Record};
+use ratio::ParsedRatio;
+use repl::ReplArgs;
+use report::ReportArgs;
+
+#[derive(Parser)]
+#[command(allow_negative_numbers = true)]
+struct Cli {
+    /// Target number to generate a literal for, eg. `10`, `-1.25`, `5/4`, `1.5e3`, `0x1F` or `2^20+3`
+    #[arg(allow_hyphen_values = true)]
+    target: Option<String>,
+
+    /// Load the target, limits, objective and algorithm from a .toml or .json file instead of the command line
+    #[arg(long, conflicts_with_all = ["target", "float", "negative", "ceiling", "fractions", "objective"])]
+    config: Option<PathBuf>,
+
+    /// Print the request as TOML (for use with --config) instead of generating a pattern
+    #[arg(long)]
+    print_config: bool,
+
+    /// Parse the target as a double and generate the exact value it represents (eg. 0.1 is not exactly 1/10). Its
+    /// numerator must be below 2^63 and its denominator at most 2^62, so eg. 1e20 and 2^-70 are rejected. The searches
+    /// can't reach long binary expansions like 0.1's denominator of 2^55, so only doubles like 0.375 find a pattern
+    #[arg(short = 'F', long)]
+    float: bool,
+
+    /// Whether to make the target negative
+    #[arg(short, long)]
+    negative: bool,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    /// Generate the simplest reachable number within this distance of the target, instead of the exact target. It's
+    /// only a fraction if the fraction flags allow it
+    #[arg(short, long)]
+    tolerance: Option<f64>,
+
+    /// How to print the result
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate every target in a file or stdin in parallel, printing one JSON object per line
+    Batch(BatchArgs),
+    /// Time each algorithm configuration over a range of targets, writing one performance dump per configuration
+    Bench(BenchArgs),
+    /// Compare two performance dumps, reporting which targets got better or worse
+    Compare(CompareArgs),
+    /// Print statistics about a pregenerated table or performance dump
+    Report(ReportArgs),
+    /// Interactively generate targets, watching the search improve, and edit patterns by hand
+    Repl(ReplArgs),
+    #[command(flatten)]
+    Generator(GeneratorOptions),
+}
+
+impl Cli {
+    fn parse_target(&self, target: &str) -> Result<Ratio<i64>, String> {
+        let target = if self.float {
+            float_target(target.parse().map_err(|_| format!("Invalid float: {target}"))?)
+                .map_err(|err| err.to_string())?
+        } else {
+            target.parse::<ParsedRatio>().map_err(|err| err.to_string())?.0
+        };
+        Ok(if self.negative { -target } else { target })
+    }
+
+    /// Builds the request from the config file if there is one, or from the command line otherwise. `target` and
+    /// `options` replace the config's values if given.
+    fn request(
+        &self,
+        target: Option<Ratio<i64>>,
+        options: Option<&GeneratorOptions>,
+    ) -> Result<GenerationRequest, String> {
+        if let Some(path) = &self.config {
+            let mut request = load_config(path)?;
+            if let Some(target) = target {
+                request.target = target;
+            }
+            if let Some(options) = options {
+                request.algorithm = options.clone();
+            }
+            return Ok(request);
+        }
+
+        let target = target.ok_or("No target given")?;
+        let options = options.cloned().ok_or("No algorithm given")?;
+
+        self.limits.apply(GenerationRequest::new(target, options), Ceiling::default()).map_err(|err| err.to_string())
+    }
+}
+
+fn load_config(path: &PathBuf) -> Result<GenerationRequest, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => GenerationRequest::from_json(&contents),
+        _ => GenerationRequest::from_toml(&contents),
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn main() -> Result<(), String> {
+    let cli = Cli::parse();
+
+    let options = match &cli.command {
+        Some(Command::Batch(args)) => return batch::run(&cli, args),
+        Some(Command::Bench(args)) => return bench::run(&cli, args),
+        Some(Command::Compare(args)) => return compare::run(&cli, args),
+        Some(Command::Report(args)) => return report::run(args),
+        Some(Command::Repl(args)) => return repl::run(&cli, args),
+        Some(Command::Generator(options)) => Some(options),
+        None => None,
+    };
+    let target = cli.target.as_deref().map(|target| cli.parse_target(target)).transpose()?;
+    let request = cli.request(target, options)?;
+
+    if cli.print_config {
+        print!("{}", request.to_toml().map_err(|err| err.to_string())?);
+        return Ok(());
+    }
+
+    let target = request.target;
+    let start = Instant::now();
+    let result = match cli.tolerance {
+        Some(tolerance) => {
+            let value = target.to_f64().ok_or_else(|| format!("Failed to convert {target} to a float"))?;
+            let options = request.algorithm.clone();
+            approximate_number_pattern(value, tolerance, request.ceiling, request.fractions, request.objective, options)
+                .map(|number| number.map(|number| (number.value(), number.number)))
+        }
+        None => request.generate().map(|number| number.map(|number| (target, number))),
+    };
+    let time = start.elapsed().as_secs_f64();
+
+    let (value, number) =
+        result.map_err(|err| err.to_string())?.ok_or_else(|| format!("No pattern found for {target}"))?;
+    cli.format.print(&Record::new(target, value, number, &request.algorithm, time)?)
+}
@@ -0,0 +1,142 @@
+use clap::ValueEnum;
+use hexnumgen::{
+    give_command, hexpattern, pattern_iota, ratio_serde, Bounds, Direction, GeneratedNumber, GeneratorOptions, GiveItem,
+};
+use num_rational::Ratio;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// Human-readable summary
+    Text,
+    /// Pretty-printed JSON object
+    Json,
+    /// JSON object on a single line
+    Jsonl,
+    /// Line of .hexpattern source, eg. <south_east,aqaaeaqaa>
+    Hexpattern,
+    /// Pattern iota SNBT
+    Iota,
+    /// /give command for a focus holding the pattern
+    GiveFocus,
+    /// /give command for a spellbook holding the pattern
+    GiveSpellbook,
+}
+
+impl Format {
+    pub fn print(self, record: &Record) -> Result<(), String> {
+        match self {
+            Self::Text => print_text(record),
+            Self::Json => println!("{}", serde_json::to_string_pretty(record).map_err(|err| err.to_string())?),
+            Self::Jsonl => println!("{}", serde_json::to_string(record).map_err(|err| err.to_string())?),
+            Self::Hexpattern => println!("{}", hexpattern(record.starting_direction()?, &record.pattern)),
+            Self::Iota => println!("{}", record.iota()?),
+            Self::GiveFocus => println!("{}", give_command(GiveItem::Focus, &record.iota()?)),
+            Self::GiveSpellbook => println!("{}", give_command(GiveItem::Spellbook, &record.iota()?)),
+        }
+        Ok(())
+    }
+}
+
+/// Machine-readable result, as printed by `--format json` and `--format jsonl`. Documented in the README, so keep them
+/// in sync.
+#[derive(Serialize)]
+pub struct Record {
+    #[serde(with = "ratio_serde")]
+    pub target: Ratio<i64>,
+    #[serde(with = "ratio_serde")]
+    pub value: Ratio<i64>,
+    pub direction: String,
+    pub pattern: String,
+    pub bounds: Bounds,
+    pub points: usize,
+    pub segments: usize,
+    pub quasi_area: u32,
+    pub algorithm: String,
+    pub options: Value,
+    pub time: f64,
+}
+
+impl Record {
+    pub fn new(
+        target: Ratio<i64>,
+        value: Ratio<i64>,
+        number: GeneratedNumber,
+        algorithm: &GeneratorOptions,
+        time: f64,
+    ) -> Result<Self, String> {
+        // split the internally tagged options back into the algorithm name and its parameters
+        let mut options = serde_json::to_value(algorithm).map_err(|err| format!("invalid algorithm options: {err}"))?;
+        if let Some(options) = options.as_object_mut() {
+            options.remove("name");
+        }
+
+        let GeneratedNumber { direction, pattern, bounds, num_points, num_segments } = number;
+        Ok(Self {
+            target,
+            value,
+            direction,
+            pattern,
+            bounds,
+            points: num_points,
+            segments: num_segments,
+            quasi_area: bounds.quasi_area(),
+            algorithm: algorithm.name().to_string(),
+            options,
+            time,
+        })
+    }
+
+    fn starting_direction(&self) -> Result<Direction, String> {
+        self.direction.parse().map_err(|err: hexnumgen::HexError| err.to_string())
+    }
+
+    fn iota(&self) -> Result<String, String> {
+        pattern_iota(self.starting_direction()?, &self.pattern).map_err(|err| err.to_string())
+    }
+}
+
+fn print_text(record: &Record) {
+    let Record { value, direction, pattern, bounds: Bounds { q, r, s }, points, segments, quasi_area, .. } = record;
+    println!(
+        "{direction} {pattern}
+     Value: {value}
+    Points: {points}
+  Segments: {segments}
+    Bounds: {q}/{r}/{s}
+Quasi-area: {quasi_area}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number() -> GeneratedNumber {
+        GeneratedNumber {
+            direction: "SOUTH_EAST".into(),
+            pattern: "aqaaw".into(),
+            bounds: Bounds { q: 1, r: 1, s: 1 },
+            num_points: 3,
+            num_segments: 2,
+        }
+    }
+
+    #[test]
+    fn record_splits_algorithm_name_from_options() {
+        let algorithm = serde_json::from_value(serde_json::json!({ "name": "beam-split", "num_threads": 3 })).unwrap();
+        let record = Record::new(Ratio::from_integer(1), Ratio::from_integer(1), number(), &algorithm, 0.5).unwrap();
+        assert_eq!(record.algorithm, "beam-split");
+        assert_eq!(record.options["num_threads"], 3);
+        assert!(record.options.get("name").is_none());
+    }
+
+    #[test]
+    fn record_keeps_named_params() {
+        let algorithm = serde_json::from_value(serde_json::json!({ "name": "custom", "depth": 4 })).unwrap();
+        let record = Record::new(Ratio::from_integer(1), Ratio::from_integer(1), number(), &algorithm, 0.0).unwrap();
+        assert_eq!(record.algorithm, "custom");
+        assert_eq!(record.options, serde_json::json!({ "depth": 4 }));
+    }
+}
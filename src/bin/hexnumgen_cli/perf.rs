@@ -0,0 +1,236 @@
+use std::{fs, path::Path};
+
+use hexnumgen::{
+    AStarSplitOptions, BeamOptions, BeamPoolOptions, BeamSplitOptions, Bounds, Direction, GeneratedNumber,
+    GeneratorOptions, NamedOptions, Objective,
+};
+use serde::{Deserialize, Serialize};
+
+/// Performance data for one algorithm configuration over a range of targets. This is the same format that
+/// `scripts/display_perf.py` reads, so keep them in sync.
+#[derive(Serialize, Deserialize)]
+pub struct PerfDump {
+    pub algorithm: String,
+    pub carryover: Option<usize>,
+    pub num_threads: Option<usize>,
+    pub data: Vec<PerfDumpItem>,
+}
+
+/// Everything except `target` and `time` is missing if the target failed. Older dumps also lack `pattern` and `bounds`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PerfDumpItem {
+    pub target: i64,
+    pub time: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segments: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub largest_dim: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<(u32, u32, u32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quasi_area: Option<u32>,
+}
+
+impl PerfDump {
+    /// Names the dump after the algorithm and the parameters it has. Named options for a built-in generator are named
+    /// the same as the built-in options, and other generators get their registered name.
+    pub fn new(options: &GeneratorOptions) -> Self {
+        let (algorithm, carryover, num_threads) = match options {
+            GeneratorOptions::Beam(BeamOptions { carryover, .. }) => ("Beam", Some(*carryover), None),
+            GeneratorOptions::BeamPool(BeamPoolOptions { carryover, num_threads, .. }) => {
+                ("BeamPool", Some(*carryover), Some(*num_threads))
+            }
+            GeneratorOptions::BeamSplit(BeamSplitOptions { carryover, num_threads, .. }) => {
+                ("BeamSplit", Some(*carryover), Some(*num_threads))
+            }
+            GeneratorOptions::AStar(_) => ("AStar", None, None),
+            GeneratorOptions::AStarSplit(AStarSplitOptions { num_threads }) => ("AStarSplit", None, Some(*num_threads)),
+            GeneratorOptions::Named(named) => {
+                if let Some(builtin) = builtin_options(named) {
+                    return Self::new(&builtin);
+                }
+                let param =
+                    |key: &str| named.params.get(key).and_then(|value| value.as_u64()).map(|value| value as usize);
+                (named.name.as_str(), param("carryover"), param("num_threads"))
+            }
+        };
+        Self { algorithm: algorithm.to_string(), carryover, num_threads, data: Vec::new() }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        serde_json::from_str(&contents).map_err(|err| format!("Failed to parse {}: {err}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let contents = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| format!("Failed to write {}: {err}", path.display()))
+    }
+
+    /// Returns the file name used for this configuration, eg. `BeamSplit_c25_t4.json`, or `BeamSplit_c25_t4_noTL.json`
+    /// if larger paths were kept.
+    pub fn filename(&self, trim_larger: bool) -> String {
+        let mut filename = self.algorithm.clone();
+        if let Some(carryover) = self.carryover {
+            filename += &format!("_c{carryover}");
+        }
+        if let Some(num_threads) = self.num_threads {
+            filename += &format!("_t{num_threads}");
+        }
+        if !trim_larger {
+            filename += "_noTL";
+        }
+        filename + ".json"
+    }
+}
+
+/// Returns the built-in options that named options refer to, if any.
+fn builtin_options(named: &NamedOptions) -> Option<GeneratorOptions> {
+    let options = serde_json::to_value(named).ok().and_then(|value| serde_json::from_value(value).ok())?;
+    (!matches!(options, GeneratorOptions::Named(_))).then_some(options)
+}
+
+impl PerfDumpItem {
+    pub fn new(target: i64, time: f64, number: Option<GeneratedNumber>) -> Self {
+        let mut item = Self {
+            target,
+            time,
+            pattern: None,
+            points: None,
+            segments: None,
+            largest_dim: None,
+            bounds: None,
+            quasi_area: None,
+        };
+        if let Some(GeneratedNumber { pattern, bounds, num_points, num_segments, .. }) = number {
+            let Bounds { q, r, s } = bounds;
+            item.pattern = Some(pattern);
+            item.points = Some(num_points);
+            item.segments = Some(num_segments);
+            item.largest_dim = Some(bounds.largest_dimension());
+            item.bounds = Some((q, r, s));
+            item.quasi_area = Some(bounds.quasi_area());
+        }
+        item
+    }
+
+    pub fn failed(&self) -> bool {
+        self.segments.is_none()
+    }
+
+    /// Returns the same metrics as [`Objective::key`], computed from the stored fields since older dumps don't have
+    /// `bounds`. If the dump is missing a field the objective needs, they're recomputed from the pattern instead.
+    /// Returns `None` if the target failed, or if the metric is unavailable because there's no pattern either.
+    pub fn key(&self, objective: Objective) -> Option<(usize, usize)> {
+        let quasi_area = self.quasi_area.map(|quasi_area| quasi_area as usize);
+        let stored = || match objective {
+            Objective::QuasiArea => Some((quasi_area?, self.segments?)),
+            Objective::Segments => Some((self.segments?, quasi_area?)),
+            Objective::LargestDimension => Some((self.largest_dim? as usize, quasi_area?)),
+            Objective::Points => Some((self.points?, self.segments?)),
+        };
+        stored().or_else(|| {
+            let pattern = self.pattern.as_ref()?;
+            let direction = if pattern.starts_with("dedd") { Direction::NorthEast } else { Direction::SouthEast };
+            hexnumgen::Path::from_pattern(direction, pattern).ok().map(|path| objective.path_key(&path))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hexnumgen::AStarOptions;
+
+    use super::*;
+
+    fn number() -> GeneratedNumber {
+        GeneratedNumber {
+            direction: "SOUTH_EAST".into(),
+            pattern: "aqaaw".into(),
+            bounds: Bounds { q: 2, r: 3, s: 4 },
+            num_points: 5,
+            num_segments: 6,
+        }
+    }
+
+    #[test]
+    fn filename_includes_parameters() {
+        let beam = GeneratorOptions::BeamSplit(BeamSplitOptions {
+            bounds: Bounds { q: 8, r: 8, s: 8 },
+            carryover: 25,
+            num_threads: 4,
+        });
+        assert_eq!(PerfDump::new(&beam).filename(true), "BeamSplit_c25_t4.json");
+        assert_eq!(PerfDump::new(&beam).filename(false), "BeamSplit_c25_t4_noTL.json");
+        assert_eq!(PerfDump::new(&GeneratorOptions::AStar(AStarOptions {})).filename(true), "AStar.json");
+
+        let named = |name: &str, params: serde_json::Value| {
+            let params = serde_json::from_value(params).unwrap();
+            PerfDump::new(&GeneratorOptions::Named(NamedOptions { name: name.into(), params })).filename(true)
+        };
+        assert_eq!(named("custom", serde_json::json!({})), "custom.json");
+        assert_eq!(named("custom", serde_json::json!({"carryover": 50, "num_threads": 2})), "custom_c50_t2.json");
+        assert_eq!(
+            named("beam-split", serde_json::json!({"carryover": 25, "num_threads": 4})),
+            "BeamSplit_c25_t4.json"
+        );
+        assert_eq!(named("astar", serde_json::json!({})), "AStar.json");
+    }
+
+    #[test]
+    fn item_stores_metrics() {
+        let item = PerfDumpItem::new(5, 0.25, Some(number()));
+        assert_eq!(item.pattern.as_deref(), Some("aqaaw"));
+        assert_eq!((item.points, item.segments), (Some(5), Some(6)));
+        assert_eq!((item.largest_dim, item.bounds, item.quasi_area), (Some(4), Some((2, 3, 4)), Some(24)));
+
+        let failed = PerfDumpItem::new(5, 0.25, None);
+        assert_eq!(serde_json::to_string(&failed).unwrap(), r#"{"target":5,"time":0.25}"#);
+    }
+
+    #[test]
+    fn dump_round_trips_through_a_file() {
+        let mut dump = PerfDump::new(&GeneratorOptions::AStar(AStarOptions {}));
+        dump.data.push(PerfDumpItem::new(5, 0.25, Some(number())));
+        dump.data.push(PerfDumpItem::new(6, 0.5, None));
+
+        let path = std::env::temp_dir().join(format!("hexnumgen-perf-{}.json", std::process::id()));
+        dump.write(&path).unwrap();
+        let read = PerfDump::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(serde_json::to_value(read).unwrap(), serde_json::to_value(&dump).unwrap());
+    }
+
+    #[test]
+    fn key_matches_objective() {
+        let item = PerfDumpItem::new(5, 0.25, Some(number()));
+        assert!(!item.failed());
+        assert_eq!(item.key(Objective::QuasiArea), Some((24, 6)));
+        assert_eq!(item.key(Objective::Segments), Some((6, 24)));
+        assert_eq!(item.key(Objective::LargestDimension), Some((4, 24)));
+        assert_eq!(item.key(Objective::Points), Some((5, 6)));
+
+        let failed = PerfDumpItem::new(5, 0.25, None);
+        assert!(failed.failed());
+        assert_eq!(failed.key(Objective::Segments), None);
+    }
+
+    #[test]
+    fn reads_older_dumps() {
+        let item: PerfDumpItem =
+            serde_json::from_str(r#"{"target":3,"time":0.1,"points":4,"segments":5,"largest_dim":2,"quasi_area":8}"#)
+                .unwrap();
+        assert_eq!((item.pattern.as_deref(), item.bounds), (None, None));
+        assert_eq!(item.key(Objective::LargestDimension), Some((2, 8)));
+
+        let without_points = PerfDumpItem { points: None, ..item.clone() };
+        assert_eq!(without_points.key(Objective::Points), None);
+        let with_pattern = PerfDumpItem { pattern: Some("aqaaw".into()), ..without_points };
+        assert_eq!(with_pattern.key(Objective::Points), Some((5, 6)));
+    }
+}
@@ -0,0 +1,61 @@
+use std::str::FromStr;
+
+use hexnumgen::{parse_expression, HexError};
+use num_rational::Ratio;
+
+/// A number given on the command line, which can be any expression supported by [`parse_expression`].
+#[derive(Clone)]
+pub struct ParsedRatio(pub Ratio<i64>);
+
+impl FromStr for ParsedRatio {
+    type Err = HexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_expression(s).map(Self)
+    }
+}
+
+/// Expands `a..b` or `a..=b` into the integers it contains, or returns `None` if `s` isn't a range.
+pub fn expand_range(s: &str) -> Result<Option<impl Iterator<Item = i64>>, String> {
+    let Some((start, end)) = s.split_once("..") else { return Ok(None) };
+    let (end, inclusive) = match end.strip_prefix('=') {
+        Some(end) => (end, true),
+        None => (end, false),
+    };
+
+    let parse = |bound: &str| bound.trim().parse::<i64>().map_err(|_| format!("Invalid range bound: {bound}"));
+    let (start, end) = (parse(start)?, parse(end)?);
+    let end = if inclusive { end.checked_add(1).ok_or("Range is too large")? } else { end };
+    Ok(Some(start..end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_ranges() {
+        assert_eq!(expand_range("1..4").unwrap().unwrap().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(expand_range("-2..=1").unwrap().unwrap().collect::<Vec<_>>(), [-2, -1, 0, 1]);
+        assert_eq!(expand_range(" 3 .. 3").unwrap().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn leaves_other_targets_alone() {
+        assert!(expand_range("5/4").unwrap().is_none());
+        assert!(expand_range("1.25").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        assert!(expand_range("1..x").is_err());
+        assert!(expand_range("1.5..3").is_err());
+        assert!(expand_range(&format!("0..={}", i64::MAX)).is_err());
+    }
+
+    #[test]
+    fn parses_expressions() {
+        assert_eq!("5/4".parse::<ParsedRatio>().unwrap().0, Ratio::new(5, 4));
+        assert!("5/".parse::<ParsedRatio>().is_err());
+    }
+}
@@ -0,0 +1,380 @@
+use std::{
+    io::{self, BufRead, Write},
+    time::Instant,
+};
+
+use clap::{Args, ValueEnum};
+use hexnumgen::{render_pattern, Angle, Bounds, Direction, GeneratorOptions, Objective, Path, SearchHooks};
+use serde_json::{json, Value};
+
+use crate::{report::OPERATIONS, Cli};
+
+#[derive(Args)]
+pub struct ReplArgs {
+    /// Algorithm to start with (defaults to beam)
+    #[command(subcommand)]
+    options: Option<GeneratorOptions>,
+}
+
+const HELP: &str = "\
+<target>                  generate a pattern for a target, eg. 1000 or 2^10-1
+algorithm <name>          switch algorithm, eg. beam, beam-pool, beam-split, astar or astar-split
+bounds <q> <r> <s>        set the bounds for beam search algorithms
+carryover <n>             set the carryover for beam search algorithms
+threads <n>               set the number of threads for parallel algorithms
+objective <objective>     set what makes one pattern better than another, eg. quasi-area or segments
+pattern [direction] <p>   load a pattern to edit, eg. aqaawaq or NORTH_EAST deddw
+append <angles>           add angles to the end of the pattern
+pop [n]                   remove the last n angles (default 1)
+set <index> <angle>       replace the angle at an index
+insert <index> <angles>   insert angles before an index
+delete <index>            remove the angle at an index
+show                      print the pattern's value trace and drawing
+settings                  print the current settings
+help                      print this message
+quit                      exit";
+
+/// Number patterns start with one of these, and the edit commands won't change them.
+const PREFIX_LEN: usize = 4;
+
+/// Checks that every character of a pattern is an angle, so it can be indexed by byte.
+fn check_angles(pattern: &str) -> Result<(), String> {
+    pattern.chars().try_for_each(|c| Angle::try_from(c).map(drop)).map_err(|err| err.to_string())
+}
+
+struct Repl<'a> {
+    cli: &'a Cli,
+    algorithm: String,
+    bounds: Bounds,
+    carryover: usize,
+    num_threads: usize,
+    objective: Option<Objective>,
+    /// The pattern being edited, from the last search or the `pattern` command
+    pattern: Option<(Direction, String)>,
+}
+
+pub fn run(cli: &Cli, args: &ReplArgs) -> Result<(), String> {
+    let mut repl = Repl {
+        cli,
+        algorithm: "beam".into(),
+        bounds: Bounds::new(8, 8, 8),
+        carryover: 25,
+        num_threads: 2,
+        objective: cli.limits.objective,
+        pattern: None,
+    };
+    if let Some(options) = &args.options {
+        repl.load_options(options)?;
+    }
+
+    println!("Type a target to generate it, or `help` for more commands.");
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().map_err(|err| err.to_string())?;
+
+        let Some(line) = lines.next() else { break };
+        let line = line.map_err(|err| format!("Failed to read stdin: {err}"))?;
+        let words: Vec<_> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {}
+            ["quit" | "exit"] => break,
+            words => {
+                if let Err(err) = repl.command(words) {
+                    println!("Error: {err}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Repl<'_> {
+    fn command(&mut self, words: &[&str]) -> Result<(), String> {
+        match words {
+            ["help"] => println!("{HELP}"),
+            ["settings"] => self.print_settings()?,
+            ["algorithm", name] => {
+                let previous = std::mem::replace(&mut self.algorithm, name.to_string());
+                if let Err(err) = self.options() {
+                    self.algorithm = previous;
+                    return Err(err);
+                }
+            }
+            ["bounds", q, r, s] => self.bounds = Bounds::new(parse(q)?, parse(r)?, parse(s)?),
+            ["carryover", carryover] => self.carryover = parse(carryover)?,
+            ["threads", num_threads] => self.num_threads = parse(num_threads)?,
+            ["objective", objective] => self.objective = Some(Objective::from_str(objective, true)?),
+            ["pattern", pattern] => self.set_pattern(default_direction(pattern), pattern)?,
+            ["pattern", direction, pattern] => {
+                self.set_pattern(direction.parse().map_err(|err: hexnumgen::HexError| err.to_string())?, pattern)?
+            }
+            ["append", angles] => self.edit(|pattern| pattern.push_str(angles))?,
+            ["pop"] => self.edit(|pattern| pattern.truncate(pattern.len() - 1))?,
+            ["pop", n] => {
+                let n: usize = parse(n)?;
+                self.edit(|pattern| pattern.truncate(pattern.len().saturating_sub(n)))?
+            }
+            ["set", index, angle] => {
+                if angle.chars().count() != 1 {
+                    return Err(format!("Expected a single angle, not {angle}"));
+                }
+                let index = self.edit_index(index, false)?;
+                self.edit(|pattern| pattern.replace_range(index..=index, angle))?
+            }
+            ["insert", index, angles] => {
+                let index = self.edit_index(index, true)?;
+                self.edit(|pattern| pattern.insert_str(index, angles))?
+            }
+            ["delete", index] => {
+                let index = self.edit_index(index, false)?;
+                self.edit(|pattern| {
+                    pattern.remove(index);
+                })?
+            }
+            ["show"] => {
+                let (direction, pattern) = self.pattern.as_ref().ok_or("No pattern loaded")?;
+                print_trace(*direction, pattern);
+            }
+            _ => self.generate(&words.join(" "))?,
+        }
+        Ok(())
+    }
+
+    fn options(&self) -> Result<GeneratorOptions, String> {
+        let options = serde_json::from_value(json!({
+            "name": self.algorithm,
+            "bounds": self.bounds,
+            "carryover": self.carryover,
+            "num_threads": self.num_threads,
+        }))
+        .map_err(|err| err.to_string())?;
+        GeneratorOptions::validate(&options).map_err(|err| err.to_string())?;
+        Ok(options)
+    }
+
+    fn load_options(&mut self, options: &GeneratorOptions) -> Result<(), String> {
+        let value = serde_json::to_value(options).map_err(|err| err.to_string())?;
+        if let Some(name) = value.get("name").and_then(Value::as_str) {
+            self.algorithm = name.to_string();
+        }
+        if let Some(bounds) = value.get("bounds") {
+            self.bounds = serde_json::from_value(bounds.clone()).map_err(|err| err.to_string())?;
+        }
+        if let Some(carryover) = value.get("carryover").and_then(Value::as_u64) {
+            self.carryover = carryover as usize;
+        }
+        if let Some(num_threads) = value.get("num_threads").and_then(Value::as_u64) {
+            self.num_threads = num_threads as usize;
+        }
+        Ok(())
+    }
+
+    fn print_settings(&self) -> Result<(), String> {
+        let Bounds { q, r, s } = self.bounds;
+        println!("algorithm: {}", self.algorithm);
+        println!("bounds:    {q} {r} {s}");
+        println!("carryover: {}", self.carryover);
+        println!("threads:   {}", self.num_threads);
+        let objective = self.objective.unwrap_or_default().to_possible_value().ok_or("Invalid objective")?;
+        println!("objective: {}", objective.get_name());
+        Ok(())
+    }
+
+    fn generate(&mut self, target: &str) -> Result<(), String> {
+        let target = self.cli.parse_target(target)?;
+        let options = self.options()?;
+        let mut request = self.cli.request(Some(target), Some(&options))?;
+        if let Some(objective) = self.objective {
+            request.objective = objective;
+        }
+
+        let start = Instant::now();
+        let hooks = SearchHooks::default().on_improvement(move |path: &Path| {
+            println!(
+                "[{:.3}s] {} segments, quasi-area {}",
+                start.elapsed().as_secs_f64(),
+                path.len(),
+                path.bounds().quasi_area()
+            );
+            if let Ok(drawing) = render_pattern(path.starting_direction(), &path.pattern()) {
+                println!("{drawing}\n");
+            }
+        });
+
+        let number = request
+            .generate_with_hooks(hooks)
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| format!("No pattern found for {target}"))?;
+        let direction = number.starting_direction().map_err(|err| err.to_string())?;
+        println!("Done in {:.3}s: {} {}", start.elapsed().as_secs_f64(), direction, number.pattern);
+        print_trace(direction, &number.pattern);
+
+        self.pattern = Some((direction, number.pattern));
+        Ok(())
+    }
+
+    fn set_pattern(&mut self, direction: Direction, pattern: &str) -> Result<(), String> {
+        check_angles(pattern)?;
+        if !matches!(pattern.get(..PREFIX_LEN), Some("aqaa" | "dedd")) {
+            return Err(format!("Number patterns start with aqaa or dedd, not {pattern}"));
+        }
+        self.pattern = Some((direction, pattern.to_string()));
+        print_trace(direction, pattern);
+        Ok(())
+    }
+
+    /// Parses an index into the current pattern for editing, which can't be inside the prefix.
+    fn edit_index(&self, index: &str, allow_end: bool) -> Result<usize, String> {
+        let (_, pattern) = self.pattern.as_ref().ok_or("No pattern loaded")?;
+        let index: usize = parse(index)?;
+        let end = if allow_end { pattern.len() } else { pattern.len() - 1 };
+        if index < PREFIX_LEN || index > end {
+            return Err(format!("Index must be between {PREFIX_LEN} and {end}"));
+        }
+        Ok(index)
+    }
+
+    fn edit(&mut self, f: impl FnOnce(&mut String)) -> Result<(), String> {
+        let (direction, pattern) = self.pattern.as_mut().ok_or("No pattern loaded")?;
+        let mut edited = pattern.clone();
+        f(&mut edited);
+        check_angles(&edited)?;
+        if edited.len() < PREFIX_LEN || edited[..PREFIX_LEN] != pattern[..PREFIX_LEN] {
+            return Err("Can't edit the aqaa/dedd prefix".into());
+        }
+        *pattern = edited;
+        print_trace(*direction, pattern);
+        Ok(())
+    }
+}
+
+/// Prints the value after each angle of a number pattern, stopping at the first invalid one, then draws the pattern.
+fn print_trace(direction: Direction, pattern: &str) {
+    let sign = if pattern.starts_with("dedd") { "-" } else { "" };
+    println!("{:>5}  {:<5} 0", "", &pattern[..PREFIX_LEN]);
+
+    let mut last = None;
+    for (index, c) in pattern.char_indices().skip(PREFIX_LEN) {
+        let operation = OPERATIONS.iter().find(|&&(op, _)| op == c).map_or("?", |(_, operation)| operation);
+        match Path::from_pattern(direction, &pattern[..=index]) {
+            Ok(path) => {
+                println!("{index:>5}  {c} {operation:>3} {sign}{}", path.value());
+                last = Some(path);
+            }
+            Err(err) => {
+                println!("{index:>5}  {c}     error: {err}");
+                return;
+            }
+        }
+    }
+
+    let path = match last {
+        Some(path) => path,
+        None => match Path::from_pattern(direction, pattern) {
+            Ok(path) => path,
+            Err(err) => return println!("error: {err}"),
+        },
+    };
+    println!(
+        "= {sign}{} ({} segments, {} points, quasi-area {})",
+        path.value(),
+        path.len(),
+        path.num_points(),
+        path.bounds().quasi_area()
+    );
+    if let Ok(drawing) = render_pattern(direction, pattern) {
+        println!("{drawing}");
+    }
+}
+
+/// Negative patterns conventionally start facing north east, and positive ones south east.
+fn default_direction(pattern: &str) -> Direction {
+    match pattern.starts_with("dedd") {
+        true => Direction::NorthEast,
+        false => Direction::SouthEast,
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("Invalid number: {s}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn with_repl(f: impl FnOnce(&mut Repl)) {
+        let cli = Cli::parse_from(["hexnumgen_cli"]);
+        let mut repl = Repl {
+            cli: &cli,
+            algorithm: "beam".into(),
+            bounds: Bounds::new(8, 8, 8),
+            carryover: 25,
+            num_threads: 2,
+            objective: None,
+            pattern: None,
+        };
+        f(&mut repl);
+    }
+
+    fn pattern<'a>(repl: &'a Repl) -> &'a str {
+        &repl.pattern.as_ref().unwrap().1
+    }
+
+    #[test]
+    fn edits_patterns() {
+        with_repl(|repl| {
+            repl.command(&["pattern", "aqaaw"]).unwrap();
+            assert_eq!(repl.pattern.as_ref().unwrap().0, Direction::SouthEast);
+            repl.command(&["append", "qa"]).unwrap();
+            assert_eq!(pattern(repl), "aqaawqa");
+            repl.command(&["pop"]).unwrap();
+            repl.command(&["set", "4", "e"]).unwrap();
+            repl.command(&["insert", "5", "ww"]).unwrap();
+            assert_eq!(pattern(repl), "aqaaewwq");
+            repl.command(&["delete", "7"]).unwrap();
+            repl.command(&["pop", "10"]).unwrap_err();
+            assert_eq!(pattern(repl), "aqaaeww");
+        });
+    }
+
+    #[test]
+    fn protects_the_prefix() {
+        with_repl(|repl| {
+            repl.command(&["append", "w"]).unwrap_err();
+            repl.command(&["pattern", "qqqqw"]).unwrap_err();
+            repl.command(&["pattern", "NORTH_EAST", "deddw"]).unwrap();
+            repl.command(&["set", "3", "w"]).unwrap_err();
+            repl.command(&["delete", "0"]).unwrap_err();
+            repl.command(&["insert", "6", "w"]).unwrap_err();
+            assert_eq!(pattern(repl), "deddw");
+        });
+    }
+
+    #[test]
+    fn rejects_non_angle_characters() {
+        with_repl(|repl| {
+            repl.command(&["pattern", "aqaaé"]).unwrap_err();
+            repl.command(&["pattern", "éaqaa"]).unwrap_err();
+            repl.command(&["pattern", "aqaaw"]).unwrap();
+            repl.command(&["append", "é"]).unwrap_err();
+            repl.command(&["set", "4", "é"]).unwrap_err();
+            repl.command(&["insert", "5", "xé"]).unwrap_err();
+            repl.command(&["pop"]).unwrap();
+            assert_eq!(pattern(repl), "aqaa");
+        });
+    }
+
+    #[test]
+    fn switches_algorithms() {
+        with_repl(|repl| {
+            repl.command(&["algorithm", "astar-split"]).unwrap();
+            assert!(matches!(repl.options().unwrap(), GeneratorOptions::AStarSplit(_)));
+            repl.command(&["algorithm", "nonexistent"]).unwrap_err();
+            assert_eq!(repl.algorithm, "astar-split");
+        });
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    fs,
+    path::PathBuf,
+};
+
+use clap::{Args, ValueEnum};
+use hexnumgen::{NumberTable, TableFormat};
+use num_rational::Ratio;
+use num_traits::ToPrimitive;
+use serde_json::Value;
+
+use crate::perf::PerfDump;
+
+#[derive(Args)]
+pub struct ReportArgs {
+    /// Table from `pregen` or performance dump from `bench` to summarize
+    input: PathBuf,
+
+    /// How to print the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    format: ReportFormat,
+
+    /// Number of worst-compressed targets to list
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    /// Human-readable tables
+    Text,
+    /// One `section,group,metric,value` row per statistic
+    Csv,
+}
+
+/// A target that was generated successfully, with the metrics the report needs.
+struct Entry {
+    target: Ratio<i64>,
+    segments: usize,
+    quasi_area: u32,
+    /// Missing in older perf dumps
+    pattern: Option<String>,
+}
+
+/// Targets whose absolute values have the same number of digits before the decimal point.
+struct Bucket {
+    digits: u32,
+    count: usize,
+    segments: Stats,
+    quasi_area: Stats,
+}
+
+struct Stats {
+    min: f64,
+    median: f64,
+    mean: f64,
+    max: f64,
+}
+
+/// Least squares fit of `segments = intercept + slope * log2(|target| + 1)`.
+struct Fit {
+    intercept: f64,
+    slope: f64,
+    r_squared: f64,
+}
+
+struct Report<'a> {
+    targets: usize,
+    failed: usize,
+    buckets: Vec<Bucket>,
+    fit: Fit,
+    /// Targets with the most segments above what the fit predicts, and how many more they have
+    worst: Vec<(&'a Entry, f64)>,
+    /// How often each angle appears after the aqaa/dedd prefix
+    operations: HashMap<char, usize>,
+}
+
+/// What each angle in the tail of a number pattern does to the value.
+pub const OPERATIONS: [(char, &str); 5] = [('w', "+1"), ('q', "+5"), ('e', "+10"), ('a', "*2"), ('d', "/2")];
+
+pub fn run(args: &ReportArgs) -> Result<(), String> {
+    let (entries, failed) = load(&args.input)?;
+    if entries.is_empty() {
+        return Err(format!("No successfully generated targets in {}", args.input.display()));
+    }
+
+    let report = Report::new(&entries, failed, args.top);
+    match args.format {
+        ReportFormat::Text => report.print_text(),
+        ReportFormat::Csv => report.print_csv(),
+    }
+    Ok(())
+}
+
+/// Reads the successful entries of a pregen table or perf dump, and counts the failed ones.
+fn load(path: &PathBuf) -> Result<(Vec<Entry>, usize), String> {
+    let format = TableFormat::from_path(path);
+    if format == TableFormat::Json {
+        let contents = fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        let value: Value =
+            serde_json::from_str(&contents).map_err(|err| format!("Failed to parse {}: {err}", path.display()))?;
+
+        if value.get("data").is_some() {
+            let dump: PerfDump =
+                serde_json::from_value(value).map_err(|err| format!("Invalid perf dump {}: {err}", path.display()))?;
+            let failed = dump.data.iter().filter(|item| item.failed()).count();
+            let entries = dump
+                .data
+                .into_iter()
+                .filter_map(|item| {
+                    Some(Entry {
+                        target: item.target.into(),
+                        segments: item.segments?,
+                        quasi_area: item.quasi_area?,
+                        pattern: item.pattern,
+                    })
+                })
+                .collect();
+            return Ok((entries, failed));
+        }
+    }
+
+    let table = NumberTable::open(path).map_err(|err| format!("Invalid table {}: {err}", path.display()))?;
+    let mut entries = Vec::new();
+    let mut failed = 0;
+    for entry in table.iter() {
+        let entry = entry.map_err(|err| format!("Invalid table {}: {err}", path.display()))?;
+        let Some(path) = entry.path().map_err(|err| format!("Invalid pattern for {}: {err}", entry.target))? else {
+            failed += 1;
+            continue;
+        };
+        entries.push(Entry {
+            target: entry.target,
+            segments: path.len(),
+            quasi_area: path.bounds().quasi_area(),
+            pattern: entry.pattern.map(|(_, pattern)| pattern),
+        });
+    }
+    Ok((entries, failed))
+}
+
+impl<'a> Report<'a> {
+    fn new(entries: &'a [Entry], failed: usize, top: usize) -> Self {
+        let mut by_digits: BTreeMap<u32, Vec<&Entry>> = BTreeMap::new();
+        for entry in entries {
+            let digits = entry.target.to_integer().unsigned_abs().checked_ilog10().map_or(0, |log| log + 1);
+            by_digits.entry(digits).or_default().push(entry);
+        }
+        let buckets = by_digits
+            .into_iter()
+            .map(|(digits, bucket)| Bucket {
+                digits,
+                count: bucket.len(),
+                segments: Stats::new(bucket.iter().map(|entry| entry.segments as f64).collect()),
+                quasi_area: Stats::new(bucket.iter().map(|entry| entry.quasi_area as f64).collect()),
+            })
+            .collect();
+
+        let fit = Fit::new(entries);
+        let mut worst: Vec<_> =
+            entries.iter().map(|entry| (entry, entry.segments as f64 - fit.predict(entry))).collect();
+        worst.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        worst.truncate(top);
+
+        let mut operations = HashMap::new();
+        for pattern in entries.iter().filter_map(|entry| entry.pattern.as_deref()) {
+            for c in pattern.chars().skip(4) {
+                *operations.entry(c).or_default() += 1;
+            }
+        }
+
+        Self { targets: entries.len(), failed, buckets, fit, worst, operations }
+    }
+
+    fn print_text(&self) {
+        println!("Targets: {} ({} failed)", self.targets, self.failed);
+
+        println!();
+        println!("Segments and quasi-area by |target| (min/median/mean/max):");
+        println!("{:>24} {:>8} {:>24} {:>28}", "|target|", "count", "segments", "quasi-area");
+        for bucket in &self.buckets {
+            println!(
+                "{:>24} {:>8} {:>24} {:>28}",
+                bucket_label(bucket.digits),
+                bucket.count,
+                bucket.segments.to_string(),
+                bucket.quasi_area.to_string()
+            );
+        }
+
+        println!();
+        println!(
+            "Growth: segments = {:.2} + {:.2} * log2(|target| + 1), R² = {:.3}",
+            self.fit.intercept, self.fit.slope, self.fit.r_squared
+        );
+
+        println!();
+        println!("Worst-compressed targets (most segments above the growth fit):");
+        println!("{:>20} {:>8} {:>8} {:>10}  pattern", "target", "segments", "excess", "quasi-area");
+        for (entry, excess) in &self.worst {
+            println!(
+                "{:>20} {:>8} {:>+8.1} {:>10}  {}",
+                entry.target.to_string(),
+                entry.segments,
+                excess,
+                entry.quasi_area,
+                entry.pattern.as_deref().unwrap_or("-")
+            );
+        }
+
+        let total: usize = self.operations.values().sum();
+        if total > 0 {
+            println!();
+            println!("Operations (excluding the aqaa/dedd prefix):");
+            for (c, operation) in OPERATIONS {
+                let count = self.operations.get(&c).copied().unwrap_or_default();
+                println!("{c} {operation:>4} {count:>12} {:>6.2}%", count as f64 / total as f64 * 100.0);
+            }
+        }
+    }
+
+    fn print_csv(&self) {
+        let row = |section: &str, group: &dyn Display, metric: &str, value: &dyn Display| {
+            println!("{section},{group},{metric},{value}");
+        };
+
+        println!("section,group,metric,value");
+        row("summary", &"", "targets", &self.targets);
+        row("summary", &"", "failed", &self.failed);
+        for bucket in &self.buckets {
+            let group = bucket_label(bucket.digits);
+            row("buckets", &group, "count", &bucket.count);
+            for (metric, stats) in [("segments", &bucket.segments), ("quasi_area", &bucket.quasi_area)] {
+                row("buckets", &group, &format!("{metric}_min"), &stats.min);
+                row("buckets", &group, &format!("{metric}_median"), &stats.median);
+                row("buckets", &group, &format!("{metric}_mean"), &stats.mean);
+                row("buckets", &group, &format!("{metric}_max"), &stats.max);
+            }
+        }
+        row("growth", &"", "intercept", &self.fit.intercept);
+        row("growth", &"", "slope", &self.fit.slope);
+        row("growth", &"", "r_squared", &self.fit.r_squared);
+        for (entry, excess) in &self.worst {
+            row("worst", &entry.target, "segments", &entry.segments);
+            row("worst", &entry.target, "excess", excess);
+            row("worst", &entry.target, "quasi_area", &entry.quasi_area);
+        }
+        for (c, _) in OPERATIONS {
+            row("operations", &c, "count", &self.operations.get(&c).copied().unwrap_or_default());
+        }
+    }
+}
+
+impl Stats {
+    fn new(mut values: Vec<f64>) -> Self {
+        values.sort_by(f64::total_cmp);
+        let middle = values.len() / 2;
+        let median = match values.len() % 2 {
+            0 => (values[middle - 1] + values[middle]) / 2.0,
+            _ => values[middle],
+        };
+        Self {
+            min: values[0],
+            median,
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{:.1}/{}", self.min, self.median, self.mean, self.max)
+    }
+}
+
+impl Fit {
+    fn new(entries: &[Entry]) -> Self {
+        let n = entries.len() as f64;
+        let points: Vec<_> = entries.iter().map(|entry| (log2(entry.target), entry.segments as f64)).collect();
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+        let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance_x: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let variance_y: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+
+        // with only one distinct target there's no slope to fit, so just predict the mean
+        let slope = if variance_x > 0.0 { covariance / variance_x } else { 0.0 };
+        let r_squared =
+            if variance_x > 0.0 && variance_y > 0.0 { covariance.powi(2) / (variance_x * variance_y) } else { 0.0 };
+        Self { intercept: mean_y - slope * mean_x, slope, r_squared }
+    }
+
+    fn predict(&self, entry: &Entry) -> f64 {
+        self.intercept + self.slope * log2(entry.target)
+    }
+}
+
+fn log2(target: Ratio<i64>) -> f64 {
+    (target.to_f64().unwrap_or_default().abs() + 1.0).log2()
+}
+
+/// Returns the range of absolute values with this many digits, eg. `10..=99`.
+fn bucket_label(digits: u32) -> String {
+    match digits {
+        0 => "< 1".to_string(),
+        _ => {
+            let start = 10u64.pow(digits - 1);
+            match 10u64.checked_pow(digits) {
+                Some(end) => format!("{start}..={}", end - 1),
+                None => format!("{start}.."),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hexnumgen::{write_table, TableEntry, TableWriteOptions};
+
+    use super::*;
+
+    fn entry(target: i64, segments: usize, pattern: Option<&str>) -> Entry {
+        Entry { target: target.into(), segments, quasi_area: segments as u32 * 2, pattern: pattern.map(Into::into) }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hexnumgen-report-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn stats_of_odd_and_even_counts() {
+        let stats = Stats::new(vec![4.0, 1.0, 3.0]);
+        assert_eq!((stats.min, stats.median, stats.max), (1.0, 3.0, 4.0));
+        assert_eq!(stats.to_string(), "1/3/2.7/4");
+        assert_eq!(Stats::new(vec![4.0, 1.0, 3.0, 2.0]).median, 2.5);
+    }
+
+    #[test]
+    fn fits_logarithmic_growth() {
+        let entries = [entry(0, 2, None), entry(1, 4, None), entry(3, 6, None), entry(-7, 8, None)];
+        let fit = Fit::new(&entries);
+        assert!((fit.intercept - 2.0).abs() < 1e-9);
+        assert!((fit.slope - 2.0).abs() < 1e-9);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+
+        let fit = Fit::new(&[entry(5, 3, None), entry(5, 5, None)]);
+        assert_eq!((fit.intercept, fit.slope, fit.r_squared), (4.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn labels_buckets() {
+        assert_eq!(bucket_label(0), "< 1");
+        assert_eq!(bucket_label(1), "1..=9");
+        assert_eq!(bucket_label(3), "100..=999");
+        assert_eq!(bucket_label(20), "10000000000000000000..");
+    }
+
+    #[test]
+    fn report_groups_targets() {
+        let entries = [
+            entry(5, 6, Some("aqaaq")),
+            entry(-8, 9, Some("deddwaaa")),
+            entry(10, 4, Some("aqaae")),
+            entry(150, 20, None),
+        ];
+        let report = Report::new(&entries, 2, 1);
+        assert_eq!((report.targets, report.failed), (4, 2));
+        assert_eq!(
+            report.buckets.iter().map(|bucket| (bucket.digits, bucket.count)).collect::<Vec<_>>(),
+            [(1, 2), (2, 1), (3, 1)]
+        );
+        assert_eq!(report.worst.len(), 1);
+        assert_eq!(report.worst[0].0.target, Ratio::from_integer(-8));
+
+        let report = Report::new(&entries, 2, 10);
+        assert_eq!(report.worst.len(), 4);
+        assert!(report.worst.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(report.operations, HashMap::from([('q', 1), ('e', 1), ('w', 1), ('a', 3)]));
+    }
+
+    #[test]
+    fn loads_tables() {
+        let path = temp_path("table.csv");
+        let entries = [
+            TableEntry::from_tail(1.into(), Some("w")),
+            TableEntry::from_tail(2.into(), Some("wa")),
+            TableEntry::from_tail(3.into(), None),
+        ];
+        let file = fs::File::create(&path).unwrap();
+        write_table(file, TableFormat::Csv, &entries, None, TableWriteOptions::default()).unwrap();
+        let (loaded, failed) = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(failed, 1);
+        assert_eq!(loaded.iter().map(|entry| entry.segments).collect::<Vec<_>>(), [6, 7]);
+        assert_eq!(loaded[1].pattern.as_deref(), Some("aqaawa"));
+    }
+
+    #[test]
+    fn loads_perf_dumps() {
+        let path = temp_path("dump.json");
+        fs::write(
+            &path,
+            r#"{"algorithm":"AStar","carryover":null,"num_threads":null,"data":[
+                {"target":1,"time":0.1,"points":5,"segments":5,"largest_dim":2,"quasi_area":8},
+                {"target":2,"time":0.1}
+            ]}"#,
+        )
+        .unwrap();
+        let (loaded, failed) = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(failed, 1);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!((loaded[0].segments, loaded[0].quasi_area), (5, 8));
+        assert!(loaded[0].pattern.is_none());
+    }
+}
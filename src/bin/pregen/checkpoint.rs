@@ -0,0 +1,239 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use hexnumgen::TableGenerator;
+use num_rational::Ratio;
+use serde::{Deserialize, Serialize};
+
+use crate::Found;
+
+/// One finished target, stored as a line of JSON. `tail` is null if every algorithm failed.
+#[derive(Serialize, Deserialize)]
+struct CheckpointEntry {
+    #[serde(with = "hexnumgen::ratio_serde")]
+    target: Ratio<i64>,
+    tail: Option<String>,
+    /// Missing in checkpoints from before tables had metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generator: Option<usize>,
+}
+
+/// A line of a checkpoint file: the generators the run uses (always the first line), a finished target, or the seconds
+/// spent generating since the previous runtime line.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CheckpointLine {
+    Generators { generators: Vec<TableGenerator> },
+    Entry(CheckpointEntry),
+    Runtime { runtime: f64 },
+}
+
+/// Appends each finished target to a file as it comes in, so an interrupted run can pick up where it left off. Lines
+/// are buffered and flushed every `interval` along with the time spent so far, so at most that much work is lost.
+pub struct Checkpoint {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    interval: Duration,
+    last_flush: Instant,
+}
+
+impl Checkpoint {
+    pub fn path(stem: &str) -> PathBuf {
+        PathBuf::from(format!("{stem}.checkpoint.jsonl"))
+    }
+
+    /// Reads the targets saved in a checkpoint file, and the time spent generating them. A line cut off by a crash is
+    /// skipped, since the target will just be generated again.
+    ///
+    /// Fails if the checkpoint was made with different `generators`, since its patterns would be labelled with the
+    /// wrong generators (and found with different settings than the rest of the table). Checkpoints from before
+    /// generators were saved are still loaded, but without saying which generator found each pattern.
+    pub fn load(path: &Path, generators: &[TableGenerator]) -> Result<(BTreeMap<Ratio<i64>, Found>, Duration)> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok((BTreeMap::new(), Duration::ZERO)),
+            Err(err) => return Err(err).with_context(|| format!("failed to open {}", path.display())),
+        };
+
+        let mut data = BTreeMap::new();
+        let mut runtime = 0.0;
+        let mut saved_generators = None;
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| format!("failed to read {}", path.display()))?;
+            match serde_json::from_str(&line) {
+                Ok(CheckpointLine::Generators { generators: saved }) => {
+                    let same = saved.len() == generators.len()
+                        && saved.iter().zip(generators).all(|(saved, generator)| saved.same_settings(generator));
+                    if !same {
+                        bail!(
+                            "{} was made with different algorithms, fallbacks or limits, so resume it with the same \
+                             options or delete it",
+                            path.display()
+                        );
+                    }
+                    saved_generators = Some(saved);
+                }
+                Ok(CheckpointLine::Entry(CheckpointEntry { target, tail, generator })) => {
+                    let generator = generator.filter(|_| saved_generators.is_some());
+                    data.insert(target, tail.map(|tail| (tail, generator)));
+                }
+                Ok(CheckpointLine::Runtime { runtime: seconds }) if seconds.is_finite() && seconds >= 0.0 => {
+                    runtime += seconds;
+                }
+                _ => {}
+            }
+        }
+        Ok((data, Duration::from_secs_f64(runtime)))
+    }
+
+    /// Opens a checkpoint file to append to, starting it with `generators` if it's new.
+    pub fn open(path: PathBuf, interval: Duration, generators: &[TableGenerator]) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let is_new = file.metadata().with_context(|| format!("failed to read {}", path.display()))?.len() == 0;
+        let mut checkpoint = Self { path, writer: BufWriter::new(file), interval, last_flush: Instant::now() };
+        if is_new {
+            checkpoint.write_line(&CheckpointLine::Generators { generators: generators.to_vec() })?;
+            checkpoint.writer.flush()?;
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn record(&mut self, target: Ratio<i64>, found: &Found) -> Result<()> {
+        let (tail, generator) = match found {
+            Some((tail, generator)) => (Some(tail.clone()), *generator),
+            None => (None, None),
+        };
+        self.write_line(&CheckpointLine::Entry(CheckpointEntry { target, tail, generator }))?;
+        if self.last_flush.elapsed() >= self.interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the time spent since the last flush and flushes everything, eg. before stopping.
+    pub fn flush(&mut self) -> Result<()> {
+        let now = Instant::now();
+        self.write_line(&CheckpointLine::Runtime { runtime: (now - self.last_flush).as_secs_f64() })?;
+        self.writer.flush()?;
+        self.last_flush = now;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &CheckpointLine) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, line)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Deletes the checkpoint file, once the finished table has been written.
+    pub fn finish(self) -> Result<()> {
+        drop(self.writer);
+        fs::remove_file(&self.path).with_context(|| format!("failed to delete {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hexnumgen::{AStarOptions, GenerationRequest, GeneratorOptions, Objective};
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hexnumgen-checkpoint-{}-{name}.jsonl", std::process::id()))
+    }
+
+    /// An A* generator followed by a fallback with `objective`.
+    fn generators(objective: Objective) -> Vec<TableGenerator> {
+        let request = GenerationRequest::new(0.into(), GeneratorOptions::AStar(AStarOptions {}));
+        vec![TableGenerator::from(&request), TableGenerator::from(&request.with_objective(objective))]
+    }
+
+    #[test]
+    fn missing_checkpoint_is_empty() {
+        let (data, runtime) = Checkpoint::load(&temp_path("missing"), &generators(Objective::Segments)).unwrap();
+        assert!(data.is_empty());
+        assert_eq!(runtime, Duration::ZERO);
+    }
+
+    #[test]
+    fn round_trips_targets_and_runtime() {
+        let path = temp_path("round-trip");
+        let mut checkpoint =
+            Checkpoint::open(path.clone(), Duration::from_secs(3600), &generators(Objective::Segments)).unwrap();
+        checkpoint.record(5.into(), &Some(("q".into(), Some(1)))).unwrap();
+        checkpoint.record(Ratio::new(1, 2), &Some(("wd".into(), None))).unwrap();
+        checkpoint.record(7.into(), &None).unwrap();
+        checkpoint.flush().unwrap();
+        drop(checkpoint);
+
+        // a resumed run appends to the same file, and a crash can cut off the last line
+        fs::write(
+            &path,
+            fs::read_to_string(&path).unwrap()
+                + "{\"runtime\":2.5}\n{\"target\":9,\"tail\":\"w\"}\n{\"target\":10,\"ta",
+        )
+        .unwrap();
+
+        let (data, runtime) = Checkpoint::load(&path, &generators(Objective::Segments)).unwrap();
+        let checkpoint = Checkpoint::open(path.clone(), Duration::ZERO, &[]).unwrap();
+        checkpoint.finish().unwrap();
+        assert!(!path.exists());
+
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[&5.into()], Some(("q".into(), Some(1))));
+        assert_eq!(data[&Ratio::new(1, 2)], Some(("wd".into(), None)));
+        assert_eq!(data[&7.into()], None);
+        assert_eq!(data[&9.into()], Some(("w".into(), None)));
+        assert!(runtime >= Duration::from_secs_f64(2.5) && runtime < Duration::from_secs(60));
+    }
+
+    #[test]
+    fn flushes_runtime_every_interval() {
+        let path = temp_path("interval");
+        let mut checkpoint = Checkpoint::open(path.clone(), Duration::ZERO, &generators(Objective::Segments)).unwrap();
+        checkpoint.record(1.into(), &Some(("w".into(), None))).unwrap();
+        checkpoint.record(2.into(), &Some(("wa".into(), None))).unwrap();
+
+        // everything is on disk without dropping the writer
+        let contents = fs::read_to_string(&path).unwrap();
+        checkpoint.finish().unwrap();
+        assert_eq!(contents.lines().filter(|line| line.starts_with("{\"runtime\":")).count(), 2);
+        assert_eq!(contents.lines().count(), 5);
+    }
+
+    #[test]
+    fn only_resumes_with_the_same_generators() {
+        let path = temp_path("generators");
+        let mut checkpoint = Checkpoint::open(path.clone(), Duration::ZERO, &generators(Objective::Segments)).unwrap();
+        checkpoint.record(5.into(), &Some(("q".into(), Some(1)))).unwrap();
+        drop(checkpoint);
+
+        let different = Checkpoint::load(&path, &generators(Objective::Points));
+        let missing_fallback = Checkpoint::load(&path, &generators(Objective::Segments)[..1]);
+        let (data, _) = Checkpoint::load(&path, &generators(Objective::Segments)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(different.unwrap_err().to_string().contains("different algorithms"));
+        assert!(missing_fallback.is_err());
+        assert_eq!(data[&5.into()], Some(("q".into(), Some(1))));
+    }
+
+    #[test]
+    fn older_checkpoints_lose_their_generators() {
+        let path = temp_path("older");
+        fs::write(&path, "{\"target\":5,\"tail\":\"q\",\"generator\":1}\n").unwrap();
+        let (data, _) = Checkpoint::load(&path, &generators(Objective::Segments)).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(data[&5.into()], Some(("q".into(), None)));
+    }
+}
@@ -0,0 +1,189 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, time::Instant};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use hexnumgen::{
+    generate_batch, GenerationRequest, GeneratorOptions, NumberTable, Objective, TableEntry, TableGenerator,
+    TableMetadata, TableWriteOptions,
+};
+use num_rational::Ratio;
+
+use crate::{describe, progress::Progress, write_table_file, Limits};
+
+#[derive(Args)]
+pub struct ImproveArgs {
+    /// Table to improve, in any format (it's rewritten in the same format, only writing the tails if it did)
+    file: PathBuf,
+
+    /// Only rerun this percentage of the targets, starting with the worst by --rank-by (failed targets come first)
+    #[arg(long, value_parser = parse_percentage)]
+    worst: Option<f64>,
+
+    /// What to rank targets by for --worst (defaults to --objective)
+    #[arg(long, value_enum)]
+    rank_by: Option<Objective>,
+
+    /// Number of threads to use
+    #[arg(short, long, default_value_t = 1)]
+    threads: usize,
+
+    /// If the table should be prettified (JSON only)
+    #[arg(short, long, default_value_t = false)]
+    pretty: bool,
+
+    #[command(flatten)]
+    limits: Limits,
+
+    /// Algorithm to rerun, usually a slower one than the table was made with
+    #[command(subcommand)]
+    options: GeneratorOptions,
+}
+
+fn parse_percentage(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(percentage) if (0.0..=100.0).contains(&percentage) => Ok(percentage),
+        _ => Err("must be a percentage from 0 to 100".into()),
+    }
+}
+
+/// Reruns a generator on the entries of a table, and replaces the ones it finds a strictly better pattern for. The
+/// generator and its runtime are added to the table's metadata.
+pub fn run(args: &ImproveArgs) -> Result<()> {
+    let start = Instant::now();
+    let table = NumberTable::open(&args.file).with_context(|| format!("failed to read {}", args.file.display()))?;
+    let mut entries = table.iter().collect::<Result<Vec<_>, _>>()?;
+    let objective = args.limits.objective();
+    let rank_by = args.rank_by.unwrap_or(objective);
+
+    // positive and negative targets have the same tail, so each magnitude only needs to be generated once
+    let mut magnitudes = BTreeMap::new();
+    for entry in &entries {
+        let key = entry.path()?.map(|path| rank_by.path_key(&path));
+        magnitudes.insert(magnitude(entry.target), key);
+    }
+    let mut ranked = Vec::from_iter(magnitudes);
+    ranked.sort_by_key(|&(_, key)| std::cmp::Reverse(key.unwrap_or((usize::MAX, usize::MAX))));
+    if let Some(worst) = args.worst {
+        ranked.truncate((ranked.len() as f64 * worst / 100.0).ceil() as usize);
+    }
+
+    let template = args.limits.request(args.options.clone())?;
+    let algorithm = template.algorithm.name();
+    let requests: Vec<_> = ranked.iter().map(|&(target, _)| GenerationRequest { target, ..template.clone() }).collect();
+
+    let mut metadata = table.metadata().cloned().unwrap_or_else(|| TableMetadata::new(Vec::new()));
+    metadata.version = env!("CARGO_PKG_VERSION").into();
+    let generator = metadata.add_generator(TableGenerator::from(&template));
+
+    let mut tails = BTreeMap::new();
+    let mut progress = Progress::new(requests.len());
+    generate_batch(requests.clone(), args.threads, |index, result| {
+        let target = requests[index].target;
+        progress.tick();
+        match result {
+            Ok(Some(number)) => {
+                tails.insert(target, number.pattern[4..].to_string());
+            }
+            Ok(None) => eprintln!("WARNING: {algorithm} found no pattern for {target}"),
+            Err(err) => eprintln!("WARNING: {algorithm} failed for {target}: {err}"),
+        }
+    });
+
+    // failed entries have no metrics, so check the whole table
+    let with_metrics = entries.iter().any(|entry| entry.metrics.is_some());
+    let with_generators = entries.iter().any(|entry| entry.generator.is_some());
+    let mut replaced = 0;
+    for entry in &mut entries {
+        let Some(tail) = tails.get(&magnitude(entry.target)) else { continue };
+        let mut new_entry = TableEntry::from_tail(entry.target, Some(tail));
+        let (old_path, new_path) = (entry.path()?, new_entry.path()?);
+        let is_better = match (&old_path, &new_path) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(old), Some(new)) => objective.path_key(new) < objective.path_key(old),
+        };
+        if !is_better {
+            continue;
+        }
+
+        println!("{}: {} -> {}", entry.target, describe(old_path.as_ref()), describe(new_path.as_ref()));
+        if with_metrics {
+            new_entry = new_entry.with_metrics()?;
+        }
+        if with_generators {
+            new_entry.generator = Some(generator);
+        }
+        *entry = new_entry;
+        replaced += 1;
+    }
+
+    println!("Improved {replaced} of {} entries", entries.len());
+    if replaced > 0 {
+        metadata.add_runtime(start.elapsed());
+        // write next to the table first, so it isn't lost if this fails partway through
+        let mut temp_path = args.file.clone().into_os_string();
+        temp_path.push(".tmp");
+        let options = TableWriteOptions {
+            pretty: args.pretty,
+            only_tail: table.only_tail(),
+            json_metadata: table.metadata().is_some(),
+        };
+        write_table_file(&PathBuf::from(&temp_path), table.format(), &entries, Some(&metadata), options)?;
+        fs::rename(&temp_path, &args.file).with_context(|| format!("failed to replace {}", args.file.display()))?;
+    }
+    Ok(())
+}
+
+fn magnitude(target: Ratio<i64>) -> Ratio<i64> {
+    if target < 0.into() {
+        -target
+    } else {
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Wrapper {
+        #[command(flatten)]
+        args: ImproveArgs,
+    }
+
+    fn improve(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("hexnumgen-improve-{}-{name}.json", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let args = Wrapper::parse_from(["improve".as_ref(), path.as_os_str(), "astar".as_ref()]).args;
+        run(&args).unwrap();
+        let improved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        improved
+    }
+
+    #[test]
+    fn keeps_only_tail_tables() {
+        let improved = improve("tails", r#"{"0":"","10":"wwwwwwwwww","11":null}"#);
+        let improved: serde_json::Value = serde_json::from_str(&improved).unwrap();
+        let improved = improved.as_object().unwrap();
+        assert_eq!(improved.keys().collect::<Vec<_>>(), ["0", "10", "11"]);
+        assert_eq!((&improved["0"], &improved["10"]), (&"".into(), &"e".into()));
+        assert!(improved["11"].is_string());
+    }
+
+    #[test]
+    fn keeps_full_patterns() {
+        let improved = improve("patterns", r#"{"-10":["NORTH_EAST","deddwwwwwwwwww"],"10":["SOUTH_EAST","aqaae"]}"#);
+        assert!(improved.contains(r#""-10":["NORTH_EAST","dedde"]"#), "{improved}");
+        assert!(improved.contains(r#""10":["SOUTH_EAST","aqaae"]"#), "{improved}");
+    }
+
+    #[test]
+    fn magnitude_ignores_sign() {
+        assert_eq!(magnitude(Ratio::new(-3, 4)), Ratio::new(3, 4));
+        assert_eq!(magnitude(5.into()), 5.into());
+    }
+}
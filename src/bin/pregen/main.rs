@@ -0,0 +1,549 @@
+mod checkpoint;
+mod improve;
+mod merge;
+mod progress;
+mod verify;
+
+use checkpoint::Checkpoint;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use hexnumgen::{
+    for_each_parallel, parse_expression, write_table, AStarOptions, Ceiling, GenerationRequest, GeneratorOptions,
+    LimitArgs, NumberTable, Objective, TableEntry, TableFormat, TableGenerator, TableMetadata, TableMetrics,
+    TableWriteOptions,
+};
+use improve::ImproveArgs;
+use merge::MergeArgs;
+use progress::Progress;
+use verify::VerifyArgs;
+
+use anyhow::{bail, Context, Result};
+use num_rational::Ratio;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde_json::Value;
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The tail of a finished target's pattern, and the index of the generator that found it in the table's metadata if
+/// that's known. `None` if every algorithm failed.
+type Found = Option<(String, Option<usize>)>;
+
+/// Generates a target with the first algorithm in `chain` that succeeds, returning the tail of its pattern and the
+/// index of the algorithm.
+fn find_pattern(target: Ratio<i64>, chain: &[GenerationRequest]) -> Found {
+    for (index, template) in chain.iter().enumerate() {
+        let request = GenerationRequest { target, ..template.clone() };
+        match request.generate() {
+            Ok(Some(number)) => return Some((number.pattern[4..].to_string(), Some(index))),
+            Ok(None) => eprintln!("WARNING: {} found no pattern for {target}", request.algorithm.name()),
+            Err(err) => eprintln!("WARNING: {} failed for {target}: {err}", request.algorithm.name()),
+        }
+    }
+    None
+}
+
+/// Reads the tails of the targets in an existing table, so they don't need to be generated again. Failed targets are
+/// left out so they're retried. Its generators and runtime are added to `metadata`.
+fn load_table(path: &Path, metadata: &mut TableMetadata) -> Result<BTreeMap<Ratio<i64>, Found>> {
+    let table = NumberTable::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if let Some(old) = table.metadata() {
+        metadata.runtime += old.runtime;
+    }
+
+    let mut data = BTreeMap::new();
+    for entry in table.iter() {
+        let entry = entry.with_context(|| format!("failed to read {}", path.display()))?;
+        if entry.pattern.is_none() {
+            continue;
+        }
+        // the tail is reused for both signs, so make sure it really decodes to the target
+        if let Some(problem) = verify::check(&entry).first() {
+            bail!("invalid entry for {} in {}: {problem}", entry.target, path.display());
+        }
+        // negative targets have the same tail as the positive ones
+        let target = if entry.target < 0.into() { -entry.target } else { entry.target };
+        let generator = remap_generator(&entry, table.metadata(), metadata);
+        data.insert(target, entry.tail().map(|tail| (tail.to_string(), generator)));
+    }
+    Ok(data)
+}
+
+/// Adds the generator of an entry from a table with metadata `from` to `to`, returning its new index.
+fn remap_generator(entry: &TableEntry, from: Option<&TableMetadata>, to: &mut TableMetadata) -> Option<usize> {
+    let generator = from?.generators.get(entry.generator?)?;
+    Some(to.add_generator(generator.clone()))
+}
+
+fn write_table_file(
+    path: &Path,
+    format: TableFormat,
+    entries: &[TableEntry],
+    metadata: Option<&TableMetadata>,
+    options: TableWriteOptions,
+) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    Ok(write_table(BufWriter::new(file), format, entries, metadata, options)?)
+}
+
+/// Summarizes a pattern for logs, or says that it failed.
+fn describe(path: Option<&hexnumgen::Path>) -> String {
+    match path {
+        Some(path) => {
+            let metrics = TableMetrics::from(path);
+            format!(
+                "{} ({} segments, {} points, quasi-area {})",
+                path.pattern(),
+                metrics.segments,
+                metrics.points,
+                metrics.quasi_area
+            )
+        }
+        None => "failed".into(),
+    }
+}
+
+/// Checks the output options before spending time on generating anything.
+fn check_output(format: TableFormat, only_tail: bool, metrics: bool, provenance: bool) -> Result<()> {
+    if only_tail && format != TableFormat::Json {
+        bail!("--only-tail only works with --format json");
+    }
+    if metrics && !matches!(format, TableFormat::Csv | TableFormat::Jsonl) {
+        bail!("--metrics only works with --format csv or jsonl");
+    }
+    if provenance && only_tail {
+        bail!("--provenance doesn't work with --only-tail");
+    }
+    if provenance && format == TableFormat::Binary {
+        bail!("--provenance doesn't work with --format binary, which always has the metadata but not the generators");
+    }
+    Ok(())
+}
+
+fn parse_ratio(s: &str) -> Result<Ratio<i64>, String> {
+    parse_expression(s).map_err(|err| err.to_string())
+}
+
+/// Parses a fallback algorithm, eg. `astar` or `beam-split:{"num_threads": 4}`.
+fn parse_fallback(s: &str) -> Result<GeneratorOptions, String> {
+    let (name, params) = s.split_once(':').unwrap_or((s, "{}"));
+    let mut params: Value = serde_json::from_str(params).map_err(|err| format!("invalid params: {err}"))?;
+    let object = params.as_object_mut().ok_or("params must be a JSON object")?;
+    object.insert("name".into(), name.into());
+
+    let options: GeneratorOptions = serde_json::from_value(params).map_err(|err| err.to_string())?;
+    options.validate().map_err(|err| err.to_string())?;
+    Ok(options)
+}
+
+/// One of several processes splitting up the targets of a run, eg. `2/4` for the second of four.
+#[derive(Clone, Copy)]
+struct Shard {
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    /// Targets are dealt out in turn, so every shard gets a similar mix of small and large ones.
+    fn owns(&self, position: usize) -> bool {
+        position % self.count == self.index - 1
+    }
+}
+
+fn parse_shard(s: &str) -> Result<Shard, String> {
+    let error = || "must look like i/n, eg. 2/4 for the second of four shards".to_string();
+    let (index, count) = s.split_once('/').ok_or_else(error)?;
+    let (index, count) = (index.trim().parse().map_err(|_| error())?, count.trim().parse().map_err(|_| error())?);
+    if index == 0 || index > count {
+        return Err(format!("shard {index} doesn't exist, it should be from 1 to {count}"));
+    }
+    Ok(Shard { index, count })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Signs {
+    /// Write each target and its negative
+    Both,
+    /// Only write the positive targets
+    Positive,
+    /// Only write the negative targets
+    Negative,
+}
+
+/// Limits for every search, shared by the commands that generate patterns.
+#[derive(Args)]
+struct Limits {
+    #[command(flatten)]
+    args: LimitArgs,
+}
+
+impl Limits {
+    fn objective(&self) -> Objective {
+        self.args.objective()
+    }
+
+    fn fractional_target(&self) -> bool {
+        self.args.fractions().fractional_target
+    }
+
+    /// Builds a request for `options` with these limits. The target should be replaced for each job. Paths larger than
+    /// the target are kept unless a ceiling is given, as the published tables were generated that way.
+    fn request(&self, options: GeneratorOptions) -> Result<GenerationRequest> {
+        options.validate()?;
+        Ok(self.args.apply(GenerationRequest::new(0.into(), options), Ceiling::Unbounded)?)
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check that every pattern in an existing table has the right value and metrics
+    Verify(VerifyArgs),
+    /// Rerun an algorithm on an existing table, replacing entries it finds better patterns for
+    Improve(ImproveArgs),
+    /// Combine several tables, eg. from each --shard, keeping the better pattern when they disagree
+    Merge(MergeArgs),
+    #[command(flatten)]
+    Generator(GeneratorOptions),
+}
+
+#[derive(Parser)]
+struct Cli {
+    /// Largest number to generate a literal for (not needed for other commands, like verify)
+    max: Option<u64>,
+
+    /// Number of threads to use
+    threads: Option<usize>,
+
+    /// Shuffle the targets with this seed, instead of generating the largest ones first
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Smallest number to generate a literal for
+    #[arg(long, default_value_t = 0)]
+    min: u64,
+
+    /// Distance between targets, eg. 1/4 to generate every quarter (must be an integer or a power of two fraction)
+    #[arg(long, value_parser = parse_ratio, default_value = "1")]
+    step: Ratio<i64>,
+
+    /// Only generate every nth target starting from the ith, eg. 2/4 for the second of four processes (combine the
+    /// tables with merge afterwards)
+    #[arg(long, value_parser = parse_shard)]
+    shard: Option<Shard>,
+
+    /// Which signs to write to the table (ignored with --only-tail)
+    #[arg(long, value_enum, default_value_t = Signs::Both)]
+    signs: Signs,
+
+    /// Algorithms to try in order if the previous ones fail, eg. `astar` or `beam-split:{"num_threads": 4}`
+    #[arg(long, value_parser = parse_fallback)]
+    fallback: Vec<GeneratorOptions>,
+
+    #[command(flatten)]
+    limits: Limits,
+
+    /// Format of the table, which is written to numbers_{max}.{extension} (or numbers_{max}_shard_{i}_of_{n}.{extension})
+    #[arg(long, value_enum, default_value_t = TableFormat::Json)]
+    format: TableFormat,
+
+    /// Only generate the "tail" of the number literal (skip aqaa/dedd, JSON only)
+    #[arg(short, long, default_value_t = false)]
+    only_tail: bool,
+
+    /// If the output should be prettified (JSON only)
+    #[arg(short, long, default_value_t = false)]
+    pretty: bool,
+
+    /// Also write the segments, points, bounds and quasi-area of each pattern (CSV and JSONL only)
+    #[arg(short, long, default_value_t = false)]
+    metrics: bool,
+
+    /// Also write which algorithm found each pattern, as an index into the generators in the table's metadata (CSV and
+    /// JSONL), or just the metadata under a `metadata` key (JSON). Binary tables always have the metadata and can't
+    /// record the generators
+    #[arg(long, default_value_t = false)]
+    provenance: bool,
+
+    /// Continue an interrupted run, skipping the targets saved in its checkpoint file (including failed ones)
+    #[arg(short, long, default_value_t = false)]
+    resume: bool,
+
+    /// Existing table to extend in any format, eg. numbers_1000.json (its targets won't be generated again, except failed ones)
+    #[arg(short, long)]
+    extend: Option<PathBuf>,
+
+    /// How often to save progress to numbers_{max}.checkpoint.jsonl, in seconds
+    #[arg(long, default_value_t = 10)]
+    checkpoint_interval: u64,
+
+    /// Algorithm to try first (defaults to astar), or another command
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+impl Cli {
+    /// Every target from --min to max, spaced by --step.
+    fn targets(&self, max: u64) -> Result<Vec<Ratio<i64>>> {
+        let step = self.step;
+        if step <= 0.into() || !step.is_integer() && !step.denom().unsigned_abs().is_power_of_two() {
+            bail!("--step must be a positive integer or power of two fraction, eg. 2 or 1/4");
+        }
+        if !step.is_integer() && !self.limits.fractional_target() {
+            bail!("fractional steps need --fractions or --fractional-target");
+        }
+        if self.min > max {
+            bail!("--min must be at most max");
+        }
+
+        let mut targets = Vec::new();
+        let mut target = Ratio::from_integer(self.min as i64);
+        while target <= Ratio::from_integer(max as i64) {
+            targets.push(target);
+            target += step;
+        }
+
+        if let Some(shard) = self.shard {
+            let owned = targets.into_iter().enumerate().filter(|&(position, _)| shard.owns(position));
+            targets = owned.map(|(_, target)| target).collect();
+        }
+        Ok(targets)
+    }
+
+    /// The name of the table and checkpoint files, without an extension.
+    fn stem(&self, max: u64) -> String {
+        match self.shard {
+            Some(Shard { index, count }) => format!("numbers_{max}_shard_{index}_of_{count}"),
+            None => format!("numbers_{max}"),
+        }
+    }
+
+    /// Builds a request for each algorithm to try, in order. The target is replaced for each job.
+    fn chain(&self) -> Result<Vec<GenerationRequest>> {
+        let first = match &self.command {
+            Some(Command::Generator(options)) => options.clone(),
+            _ => GeneratorOptions::AStar(AStarOptions {}),
+        };
+        [first].into_iter().chain(self.fallback.iter().cloned()).map(|options| self.limits.request(options)).collect()
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::Verify(args)) => return verify::run(args),
+        Some(Command::Improve(args)) => return improve::run(args),
+        Some(Command::Merge(args)) => return merge::run(args),
+        _ => (),
+    }
+    let Some(max) = cli.max else { bail!("MAX is required to generate a table") };
+
+    check_output(cli.format, cli.only_tail, cli.metrics, cli.provenance)?;
+    let targets = cli.targets(max)?;
+    let chain = cli.chain()?;
+
+    let checkpoint_path = Checkpoint::path(&cli.stem(max));
+    if checkpoint_path.exists() && !cli.resume {
+        bail!("{} already exists, pass --resume to continue that run or delete it", checkpoint_path.display());
+    }
+
+    // the chain is added first, so the indices saved in the checkpoint still match (it's only loaded if it was made
+    // with the same chain)
+    let generators: Vec<_> = chain.iter().map(TableGenerator::from).collect();
+    let mut metadata = TableMetadata::new(generators.clone());
+    let mut all_data = match &cli.extend {
+        Some(path) => load_table(path, &mut metadata)?,
+        None => BTreeMap::new(),
+    };
+    let (checkpoint_data, checkpoint_runtime) = Checkpoint::load(&checkpoint_path, &generators)?;
+    all_data.extend(checkpoint_data);
+    metadata.add_runtime(checkpoint_runtime);
+    all_data.retain(|target, _| targets.binary_search(target).is_ok());
+
+    let mut all_targets = Vec::from_iter(targets.iter().copied().filter(|target| !all_data.contains_key(target)));
+    if !all_data.is_empty() {
+        println!("Skipping {} targets that are already done", all_data.len());
+    }
+
+    let interval = Duration::from_secs(cli.checkpoint_interval);
+    let mut checkpoint = Checkpoint::open(checkpoint_path, interval, &generators)?;
+    match cli.seed {
+        Some(seed) => all_targets.shuffle(&mut StdRng::seed_from_u64(seed)),
+        // larger targets usually take longer, so start them first rather than leaving one thread with them at the end
+        None => all_targets.sort_by_key(|&target| Reverse(target)),
+    }
+
+    let start = Instant::now();
+    let mut progress = Progress::new(all_targets.len());
+    let mut checkpoint_failed = false;
+    let find = |target| (target, find_pattern(target, &chain));
+    for_each_parallel(all_targets, cli.threads.unwrap_or(1), find, |_, (target, found)| {
+        progress.tick();
+        // keep going if the checkpoint can't be saved, since the table can still be written at the end
+        match checkpoint.record(target, &found) {
+            Err(err) if !checkpoint_failed => {
+                eprintln!(
+                    "WARNING: failed to save checkpoint, so progress will be lost if this run is interrupted: {err}"
+                );
+                checkpoint_failed = true;
+            }
+            _ => (),
+        }
+        all_data.insert(target, found);
+    });
+    metadata.add_runtime(start.elapsed());
+    // save the runtime as well, in case writing the table fails and the run is resumed
+    if checkpoint.flush().is_err() && !checkpoint_failed {
+        eprintln!("WARNING: failed to save checkpoint, so progress will be lost if writing the table fails");
+    }
+
+    let failed = all_data.values().filter(|found| found.is_none()).count();
+    if failed > 0 {
+        eprintln!("WARNING: {failed} of {} targets failed, and are null in the table", all_data.len());
+    }
+
+    let mut entries = Vec::new();
+    for (target, found) in all_data {
+        let (tail, generator) = match &found {
+            Some((tail, generator)) => (Some(tail.as_str()), generator.filter(|_| cli.provenance)),
+            None => (None, None),
+        };
+        let entry = |target| TableEntry { generator, ..TableEntry::from_tail(target, tail) };
+        if cli.only_tail || cli.signs != Signs::Negative || target == 0.into() {
+            entries.push(entry(target));
+        }
+        if !cli.only_tail && cli.signs != Signs::Positive && target != 0.into() {
+            entries.push(entry(-target));
+        }
+    }
+    if cli.metrics {
+        entries = entries.into_iter().map(TableEntry::with_metrics).collect::<Result<_, _>>()?;
+    }
+    let path = PathBuf::from(format!("{}.{}", cli.stem(max), cli.format.extension()));
+    let options = TableWriteOptions { pretty: cli.pretty, only_tail: cli.only_tail, json_metadata: cli.provenance };
+    write_table_file(&path, cli.format, &entries, Some(&metadata), options)?;
+    checkpoint.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use hexnumgen::Direction;
+
+    use super::*;
+
+    fn write_temp_table(name: &str, entries: &[TableEntry], metadata: Option<&TableMetadata>) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("hexnumgen-pregen-{}-{name}.jsonl", std::process::id()));
+        write_table_file(&path, TableFormat::Jsonl, entries, metadata, TableWriteOptions::default()).unwrap();
+        path
+    }
+
+    fn generator() -> TableGenerator {
+        TableGenerator::from(&GenerationRequest::new(0.into(), GeneratorOptions::AStar(AStarOptions {})))
+    }
+
+    #[test]
+    fn load_table_reads_tails_and_runtime() {
+        let mut old = TableMetadata::new(vec![generator()]);
+        old.runtime = 12.5;
+        let entries = [
+            TableEntry { generator: Some(0), ..TableEntry::from_tail((-2).into(), Some("wa")) },
+            TableEntry::from_tail(5.into(), Some("q")),
+            TableEntry::from_tail(7.into(), None),
+        ];
+        let path = write_temp_table("valid", &entries, Some(&old));
+
+        let mut metadata = TableMetadata::new(Vec::new());
+        let data = load_table(&path, &mut metadata).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(metadata.runtime, 12.5);
+        assert_eq!(metadata.generators.len(), 1);
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[&2.into()], Some(("wa".into(), Some(0))));
+        assert_eq!(data[&5.into()], Some(("q".into(), None)));
+    }
+
+    #[test]
+    fn load_table_rejects_wrong_patterns() {
+        for (name, entry) in [
+            ("wrong-value", TableEntry::from_tail(5.into(), Some("w"))),
+            ("invalid-angle", TableEntry::new(5.into(), Some((Direction::SouthEast, "aqaaqx".into())))),
+            ("no-prefix", TableEntry::new(1.into(), Some((Direction::SouthEast, "wwww".into())))),
+        ] {
+            let path = write_temp_table(name, &[entry], None);
+            let result = load_table(&path, &mut TableMetadata::new(Vec::new()));
+            fs::remove_file(&path).unwrap();
+            assert!(result.is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn generates_fractional_steps() {
+        let cli = Cli::parse_from(["pregen", "3", "--step", "1/4", "-f"]);
+        let targets = cli.targets(3).unwrap();
+        assert_eq!(targets.len(), 13);
+        let chain = cli.chain().unwrap();
+        for target in targets {
+            let (tail, _) = find_pattern(target, &chain).unwrap_or_else(|| panic!("no pattern for {target}"));
+            let path = hexnumgen::Path::from_pattern(Direction::SouthEast, &format!("aqaa{tail}")).unwrap();
+            assert_eq!(path.value(), Ratio::new(*target.numer() as u64, *target.denom() as u64));
+        }
+    }
+
+    #[test]
+    fn fractional_steps_need_fractions() {
+        assert!(Cli::parse_from(["pregen", "3", "--step", "1/4"]).targets(3).is_err());
+        assert!(Cli::parse_from(["pregen", "3", "--step", "1/3", "-f"]).targets(3).is_err());
+    }
+
+    #[test]
+    fn keeps_larger_paths_by_default() {
+        let ceilings = |args: &[&str]| -> Vec<Ceiling> {
+            let cli = Cli::parse_from(["pregen", "3", "--fallback", "beam"].iter().chain(args));
+            cli.chain().unwrap().into_iter().map(|request| request.ceiling).collect()
+        };
+        assert_eq!(ceilings(&[]), [Ceiling::Unbounded, Ceiling::Unbounded]);
+        assert_eq!(ceilings(&["--max-multiple", "1"]), [Ceiling::Multiple(1.into()), Ceiling::Multiple(1.into())]);
+    }
+
+    #[test]
+    fn rejects_output_options_the_format_cant_write() {
+        assert!(check_output(TableFormat::Jsonl, false, true, true).is_ok());
+        assert!(check_output(TableFormat::Json, false, false, true).is_ok());
+        assert!(check_output(TableFormat::Json, true, false, true).is_err());
+        assert!(check_output(TableFormat::Json, false, true, false).is_err());
+        assert!(check_output(TableFormat::Binary, false, false, true).is_err());
+        assert!(check_output(TableFormat::Binary, false, false, false).is_ok());
+    }
+
+    #[test]
+    fn parses_shards() {
+        let shard = parse_shard(" 2 / 4 ").unwrap();
+        assert_eq!((shard.index, shard.count), (2, 4));
+        for invalid in ["0/4", "5/4", "2", "a/4", "2/"] {
+            assert!(parse_shard(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn shards_split_targets_evenly() {
+        let all = Cli::parse_from(["pregen", "20"]).targets(20).unwrap();
+        let mut combined = Vec::new();
+        for index in 1..=3 {
+            let shard = format!("{index}/3");
+            let cli = Cli::parse_from(["pregen", "20", "--shard", &shard]);
+            assert_eq!(cli.stem(20), format!("numbers_20_shard_{index}_of_3"));
+            let targets = cli.targets(20).unwrap();
+            assert!((6..=7).contains(&targets.len()));
+            combined.extend(targets);
+        }
+        combined.sort();
+        assert_eq!(combined, all);
+        assert_eq!(Cli::parse_from(["pregen", "20"]).stem(20), "numbers_20");
+    }
+}
@@ -0,0 +1,244 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use hexnumgen::{NumberTable, Objective, TableEntry, TableFormat, TableMetadata, TableWriteOptions};
+use num_rational::Ratio;
+
+use crate::{check_output, describe, remap_generator, write_table_file};
+
+#[derive(Args)]
+pub struct MergeArgs {
+    /// Tables to merge, in any format
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Where to write the merged table
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Format of the merged table (defaults to the one matching the extension of --output)
+    #[arg(long, value_enum)]
+    format: Option<TableFormat>,
+
+    /// What makes one pattern better than another, for targets that have different patterns in different tables
+    #[arg(long, value_enum)]
+    objective: Option<Objective>,
+
+    /// If the output should be prettified (JSON only)
+    #[arg(short, long, default_value_t = false)]
+    pretty: bool,
+
+    /// Only write the tails of the patterns (JSON only)
+    #[arg(long, default_value_t = false)]
+    only_tail: bool,
+
+    /// Also write the segments, points, bounds and quasi-area of each pattern (CSV and JSONL only)
+    #[arg(short, long, default_value_t = false)]
+    metrics: bool,
+}
+
+/// Combines tables, keeping the better pattern for targets that are in more than one. A failed entry never replaces a
+/// pattern. The generators and runtimes of every table are combined too, so entries still point to the generator that
+/// found them.
+pub fn run(args: &MergeArgs) -> Result<()> {
+    let format = args.format.unwrap_or_else(|| TableFormat::from_path(&args.output));
+    check_output(format, args.only_tail, args.metrics, false)?;
+    let objective = args.objective.unwrap_or_default();
+
+    // each entry is stored with the index of the file it came from, for reporting conflicts
+    let mut merged: BTreeMap<Ratio<i64>, (TableEntry, usize)> = BTreeMap::new();
+    let mut conflicts = 0;
+    let mut metadata = TableMetadata::new(Vec::new());
+    // JSON tables only get metadata if one of the JSON inputs had it, so merged shards stay plain maps
+    let mut json_metadata = false;
+    for (file_index, file) in args.files.iter().enumerate() {
+        let table = NumberTable::open(file).with_context(|| format!("failed to read {}", file.display()))?;
+        println!("Read {} entries from {}", table.len(), file.display());
+        if let Some(old) = table.metadata() {
+            metadata.runtime += old.runtime;
+            json_metadata |= table.format() == TableFormat::Json;
+        }
+
+        for entry in table.iter() {
+            let entry = entry.with_context(|| format!("failed to read {}", file.display()))?;
+            let entry = TableEntry { generator: remap_generator(&entry, table.metadata(), &mut metadata), ..entry };
+            let Some((old, old_index)) = merged.get(&entry.target) else {
+                merged.insert(entry.target, (entry, file_index));
+                continue;
+            };
+            if old.pattern == entry.pattern {
+                continue;
+            }
+
+            let invalid =
+                |index: usize| format!("invalid pattern for {} in {}", entry.target, args.files[index].display());
+            let old_path = old.path().with_context(|| invalid(*old_index))?;
+            let new_path = entry.path().with_context(|| invalid(file_index))?;
+            let replace = match (old_path, new_path) {
+                (_, None) => false,
+                (None, Some(_)) => true,
+                (Some(old_path), Some(new_path)) => {
+                    conflicts += 1;
+                    let better = objective.path_key(&new_path) < objective.path_key(&old_path);
+                    let kept = if better { file } else { &args.files[*old_index] };
+                    println!(
+                        "{}: {} has {}, {} has {}, keeping {}",
+                        entry.target,
+                        args.files[*old_index].display(),
+                        describe(Some(&old_path)),
+                        file.display(),
+                        describe(Some(&new_path)),
+                        kept.display()
+                    );
+                    better
+                }
+            };
+            if replace {
+                merged.insert(entry.target, (entry, file_index));
+            }
+        }
+    }
+
+    // metrics from the inputs are dropped so they're either recorded for every entry or none of them
+    let entries = merged
+        .into_values()
+        .map(|(entry, _)| match args.metrics {
+            true => entry.with_metrics(),
+            false => Ok(TableEntry { metrics: None, ..entry }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let failed = entries.iter().filter(|entry| entry.pattern.is_none()).count();
+    println!("Merged {} entries ({conflicts} conflicts, {failed} failed)", entries.len());
+    let options = TableWriteOptions { pretty: args.pretty, only_tail: args.only_tail, json_metadata };
+    write_table_file(&args.output, format, &entries, Some(&metadata), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::Parser;
+    use hexnumgen::{AStarOptions, GenerationRequest, GeneratorOptions, TableGenerator};
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Wrapper {
+        #[command(flatten)]
+        args: MergeArgs,
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hexnumgen-merge-{}-{name}", std::process::id()))
+    }
+
+    /// Writes a table found by a generator with `objective`, so each input has a different generator.
+    fn write_input(name: &str, objective: Objective, runtime: f64, entries: &[(i64, Option<&str>)]) -> PathBuf {
+        let request =
+            GenerationRequest::new(0.into(), GeneratorOptions::AStar(AStarOptions {})).with_objective(objective);
+        let metadata = TableMetadata { runtime, ..TableMetadata::new(vec![TableGenerator::from(&request)]) };
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|&(target, tail)| TableEntry {
+                generator: tail.map(|_| 0),
+                ..TableEntry::from_tail(target.into(), tail)
+            })
+            .collect();
+        let path = temp_path(name);
+        write_table_file(&path, TableFormat::Jsonl, &entries, Some(&metadata), TableWriteOptions::default()).unwrap();
+        path
+    }
+
+    #[test]
+    fn keeps_better_patterns() {
+        let first =
+            write_input("first.jsonl", Objective::QuasiArea, 1.0, &[(1, Some("w")), (2, None), (10, Some("e"))]);
+        let second = write_input(
+            "second.jsonl",
+            Objective::Segments,
+            2.5,
+            &[(2, Some("wa")), (10, Some("wwwwwwwwww")), (11, None), (5, Some("q"))],
+        );
+        let output = temp_path("merged.jsonl");
+        let args = Wrapper::parse_from([
+            "merge".as_ref(),
+            first.as_os_str(),
+            second.as_os_str(),
+            "--output".as_ref(),
+            output.as_os_str(),
+            "--metrics".as_ref(),
+        ])
+        .args;
+        run(&args).unwrap();
+        let table = NumberTable::open(&output).unwrap();
+        for path in [first, second, output] {
+            fs::remove_file(path).unwrap();
+        }
+
+        let tail = |target: i64| table.get(target.into()).unwrap().map(|entry| entry.tail().map(str::to_string));
+        assert_eq!(tail(1), Some(Some("w".into())));
+        assert_eq!(tail(2), Some(Some("wa".into())));
+        assert_eq!(tail(5), Some(Some("q".into())));
+        assert_eq!(tail(10), Some(Some("e".into())));
+        assert_eq!(tail(11), Some(None));
+
+        let metadata = table.metadata().unwrap();
+        assert_eq!(metadata.runtime, 3.5);
+        assert_eq!(metadata.generators.len(), 2);
+        let generator = |target: i64| table.get(target.into()).unwrap().unwrap().generator;
+        assert_eq!((generator(1), generator(2), generator(10)), (Some(0), Some(1), Some(0)));
+        assert!(table.iter().all(|entry| {
+            let entry = entry.unwrap();
+            entry.metrics.is_some() == entry.pattern.is_some()
+        }));
+    }
+
+    #[test]
+    fn json_output_stays_a_plain_map() {
+        let input = write_input("plain.jsonl", Objective::QuasiArea, 1.0, &[(1, Some("w"))]);
+        let output = temp_path("plain.json");
+        let args =
+            Wrapper::parse_from(["merge".as_ref(), input.as_os_str(), "--output".as_ref(), output.as_os_str()]).args;
+        run(&args).unwrap();
+        let json = fs::read_to_string(&output).unwrap();
+        let table = NumberTable::open(&output).unwrap();
+
+        // merging a JSON table with metadata keeps it
+        let merged = temp_path("merged.json");
+        let options = TableWriteOptions { json_metadata: true, ..Default::default() };
+        let input_table = NumberTable::open(&input).unwrap();
+        let entries = input_table.iter().collect::<Result<Vec<_>, _>>().unwrap();
+        write_table_file(&output, TableFormat::Json, &entries, input_table.metadata(), options).unwrap();
+        let args =
+            Wrapper::parse_from(["merge".as_ref(), output.as_os_str(), "--output".as_ref(), merged.as_os_str()]).args;
+        run(&args).unwrap();
+        let merged_table = NumberTable::open(&merged).unwrap();
+        for path in [input, output, merged] {
+            fs::remove_file(path).unwrap();
+        }
+
+        assert_eq!(json, r#"{"1":["SOUTH_EAST","aqaaw"]}"#);
+        assert!(table.metadata().is_none());
+        assert_eq!(merged_table.metadata().map(|metadata| metadata.runtime), Some(1.0));
+    }
+
+    #[test]
+    fn checks_output_options() {
+        let input = write_input("options.jsonl", Objective::QuasiArea, 0.0, &[(1, Some("w"))]);
+        let output = temp_path("options.csv");
+        let args = Wrapper::parse_from([
+            "merge".as_ref(),
+            input.as_os_str(),
+            "--output".as_ref(),
+            output.as_os_str(),
+            "--only-tail".as_ref(),
+        ])
+        .args;
+        let result = run(&args);
+        fs::remove_file(input).unwrap();
+        assert!(result.is_err());
+        assert!(!output.exists());
+    }
+}
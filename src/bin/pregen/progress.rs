@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+const PRINT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Prints how many targets are done, how fast they're going and when they should all be done. Only prints once per
+/// second, so fast runs don't flood the terminal.
+pub struct Progress {
+    total: usize,
+    done: usize,
+    start: Instant,
+    last_print: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self { total, done: 0, start: Instant::now(), last_print: None }
+    }
+
+    /// Records that one more target is done.
+    pub fn tick(&mut self) {
+        if let Some(status) = self.tick_at(Instant::now()) {
+            println!("{status}");
+        }
+    }
+
+    /// Records that one more target was done at `now`, returning the line to print if it's time for one.
+    fn tick_at(&mut self, now: Instant) -> Option<String> {
+        self.done += 1;
+        if self.done < self.total && self.last_print.is_some_and(|last| now - last < PRINT_INTERVAL) {
+            return None;
+        }
+        self.last_print = Some(now);
+
+        let elapsed = (now - self.start).as_secs_f64();
+        let rate = self.done as f64 / elapsed.max(f64::EPSILON);
+        let remaining = (self.total - self.done) as f64 / rate;
+        Some(format!(
+            "{}/{} ({:.1}%), {rate:.1} targets/s, {} elapsed, ETA {}",
+            self.done,
+            self.total,
+            self.done as f64 / self.total as f64 * 100.0,
+            format_duration(elapsed),
+            format_duration(remaining)
+        ))
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, s) => format!("{h}h{m:02}m{s:02}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_at_most_once_per_interval() {
+        let mut progress = Progress::new(4);
+        let start = progress.start;
+        let at = |seconds| start + Duration::from_secs_f64(seconds);
+
+        assert_eq!(progress.tick_at(at(2.0)).unwrap(), "1/4 (25.0%), 0.5 targets/s, 2s elapsed, ETA 6s");
+        assert_eq!(progress.tick_at(at(2.5)), None);
+        assert_eq!(progress.tick_at(at(3.0)).unwrap(), "3/4 (75.0%), 1.0 targets/s, 3s elapsed, ETA 1s");
+        // the last target is always printed
+        assert_eq!(progress.tick_at(at(3.1)).unwrap(), "4/4 (100.0%), 1.3 targets/s, 3s elapsed, ETA 0s");
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(0.0), "0s");
+        assert_eq!(format_duration(59.4), "59s");
+        assert_eq!(format_duration(59.6), "1m00s");
+        assert_eq!(format_duration(61.0), "1m01s");
+        assert_eq!(format_duration(3600.0), "1h00m00s");
+        assert_eq!(format_duration(90061.0), "25h01m01s");
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use hexnumgen::{NonZeroSign, NumberTable, Path, TableEntry, TableMetrics};
+use num_rational::Ratio;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Table to check, in any format
+    file: PathBuf,
+}
+
+/// Decodes every entry of a table and prints the ones that are wrong, failing if there are any.
+pub fn run(args: &VerifyArgs) -> Result<()> {
+    let table = NumberTable::open(&args.file).with_context(|| format!("failed to read {}", args.file.display()))?;
+
+    let (mut failed, mut problems) = (0, 0);
+    for entry in table.iter() {
+        let entry = entry.with_context(|| format!("failed to read {}", args.file.display()))?;
+        if entry.pattern.is_none() {
+            failed += 1;
+        }
+        for problem in check(&entry) {
+            println!("{}: {problem}", entry.target);
+            problems += 1;
+        }
+    }
+
+    println!("Checked {} entries ({failed} failed to generate)", table.len());
+    if problems > 0 {
+        bail!("found {problems} problems in {}", args.file.display());
+    }
+    Ok(())
+}
+
+/// Returns everything wrong with an entry, or nothing if it failed to generate.
+pub fn check(entry: &TableEntry) -> Vec<String> {
+    let Some((direction, pattern)) = &entry.pattern else { return Vec::new() };
+    let mut problems = Vec::new();
+
+    // zero is written as aqaa, but dedd is also correct
+    let sign = NonZeroSign::from(entry.target);
+    let signs = if entry.target == 0.into() { vec![sign, NonZeroSign::Negative] } else { vec![sign] };
+    let prefix_matches = signs.into_iter().any(|sign| {
+        let zero = Path::zero(sign);
+        *direction == zero.starting_direction() && pattern.starts_with(&zero.pattern())
+    });
+    if !prefix_matches {
+        let zero = Path::zero(sign);
+        problems.push(format!(
+            "`{direction} {pattern}` should start with `{} {}`",
+            zero.starting_direction(),
+            zero.pattern()
+        ));
+    }
+
+    // decoding checks the prefix, the angles and that no segment repeats
+    let path = match Path::from_pattern(*direction, pattern) {
+        Ok(path) => path,
+        Err(err) => {
+            problems.push(format!("invalid pattern `{pattern}`: {err}"));
+            return problems;
+        }
+    };
+
+    let magnitude = Ratio::new(entry.target.numer().unsigned_abs(), entry.target.denom().unsigned_abs());
+    if path.value() != magnitude {
+        let sign = if pattern.starts_with("dedd") { "-" } else { "" };
+        problems.push(format!("`{pattern}` is {sign}{}", path.value()));
+    }
+
+    if let Some(recorded) = entry.metrics {
+        let actual = TableMetrics::from(&path);
+        for (name, recorded, actual) in [
+            ("segments", recorded.segments, actual.segments),
+            ("points", recorded.points, actual.points),
+            ("q", recorded.bounds.q as usize, actual.bounds.q as usize),
+            ("r", recorded.bounds.r as usize, actual.bounds.r as usize),
+            ("s", recorded.bounds.s as usize, actual.bounds.s as usize),
+            ("quasi-area", recorded.quasi_area as usize, actual.quasi_area as usize),
+        ] {
+            if recorded != actual {
+                problems.push(format!("recorded {name} is {recorded}, but should be {actual}"));
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use hexnumgen::Direction;
+
+    use super::*;
+
+    fn entry(target: i64, direction: Direction, pattern: &str) -> TableEntry {
+        TableEntry::new(target.into(), Some((direction, pattern.into())))
+    }
+
+    #[test]
+    fn accepts_correct_entries() {
+        assert!(check(&entry(5, Direction::SouthEast, "aqaaq")).is_empty());
+        assert!(check(&entry(-5, Direction::NorthEast, "deddq")).is_empty());
+        assert!(check(&entry(0, Direction::SouthEast, "aqaa")).is_empty());
+        assert!(check(&entry(0, Direction::NorthEast, "dedd")).is_empty());
+        assert!(check(&TableEntry::new(3.into(), None)).is_empty());
+
+        let half = TableEntry::from_tail(Ratio::new(1, 2), Some("wd"));
+        assert!(check(&half.with_metrics().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn reports_wrong_prefixes() {
+        let problems = check(&entry(5, Direction::NorthEast, "deddq"));
+        assert_eq!(problems, ["`NORTH_EAST deddq` should start with `SOUTH_EAST aqaa`"]);
+        assert_eq!(check(&entry(5, Direction::East, "aqaaq")).len(), 1);
+    }
+
+    #[test]
+    fn reports_wrong_values() {
+        assert_eq!(check(&entry(6, Direction::SouthEast, "aqaaq")), ["`aqaaq` is 5"]);
+    }
+
+    #[test]
+    fn reports_invalid_patterns() {
+        let problems = check(&entry(5, Direction::SouthEast, "aqaaqx"));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("invalid pattern `aqaaqx`"));
+    }
+
+    #[test]
+    fn reports_wrong_metrics() {
+        let mut entry = entry(5, Direction::SouthEast, "aqaaq").with_metrics().unwrap();
+        let metrics = entry.metrics.as_mut().unwrap();
+        metrics.segments += 1;
+        metrics.quasi_area = 0;
+        let segments = metrics.segments;
+        assert_eq!(
+            check(&entry),
+            [
+                format!("recorded segments is {segments}, but should be {}", segments - 1),
+                format!(
+                    "recorded quasi-area is 0, but should be {}",
+                    entry.path().unwrap().unwrap().bounds().quasi_area()
+                ),
+            ]
+        );
+    }
+}
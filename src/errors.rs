@@ -1,3 +1,5 @@
+use num_rational::Ratio;
+use pyo3::{exceptions::PyValueError, PyErr};
 use thiserror::Error;
 
 use crate::hex_math::{Angle, Segment};
@@ -6,6 +8,8 @@ use crate::hex_math::{Angle, Segment};
 pub enum HexError {
     #[error("invalid character `{0}`")]
     InvalidChar(char),
+    #[error("invalid direction `{0}`")]
+    InvalidDirection(String),
     #[error("invalid angle `{0:?}`")]
     InvalidAngle(Angle),
     #[error("invalid angle `{0:?}` for number `{1}`")]
@@ -14,6 +18,46 @@ pub enum HexError {
     SegmentAlreadyExists(Segment),
     #[error("value is valid but would be outside of PathLimits")]
     OutOfLimits,
+    #[error("value is too large or precise to be represented")]
+    Overflow,
+    #[error("target `{0}` is not an integer, but fractions are disabled")]
+    FractionalTarget(Ratio<i64>),
+    #[error("target `{0}` is unreachable because its denominator is not a power of two")]
+    UnreachableTarget(Ratio<i64>),
+    #[error("target `{0}` is larger than the ceiling")]
+    CeilingBelowTarget(Ratio<i64>),
+    #[error("target `{0}` needs a ceiling of at least `{1}`, since every path to it passes through that value")]
+    CeilingBelowHalvingStart(Ratio<i64>, Ratio<u64>),
+    #[error("expected a non-negative number, got `{0}`")]
+    Negative(Ratio<i64>),
+    #[error("target `{0}` needs fractional intermediate values with denominators up to `{1}`")]
+    IntermediateFractionsRequired(Ratio<i64>, u64),
+    #[error("no reachable value within `{1:?}` of `{0:?}`")]
+    NoApproximation(f64, f64),
+    #[error("`{0:?}` can't be represented exactly as a ratio of 64-bit integers (its numerator must be below 2^63 and its denominator at most 2^62)")]
+    UnrepresentableFloat(f64),
+    #[error("invalid generator options: {0}")]
+    InvalidOptions(String),
+    #[error("no generator named `{0}` is registered")]
+    UnknownGenerator(String),
+    #[error("`{0}` is not a number pattern (must start with `aqaa` or `dedd`)")]
+    NotANumber(String),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid expression `{0}` at position {1}: {2}")]
+    InvalidExpression(String, usize, String),
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("invalid table: {0}")]
+    InvalidTable(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 pub type HexResult<T> = Result<T, HexError>;
+
+impl From<HexError> for PyErr {
+    fn from(err: HexError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
@@ -0,0 +1,343 @@
+//! Parses and exactly evaluates arithmetic expressions like `2^20+3`, `-0x1F/4` or `1.5e3`.
+//!
+//! Grammar, from lowest to highest precedence:
+//!
+//! ```text
+//! expr   = term (("+" | "-") term)*
+//! term   = unary (("*" | "/") unary)*
+//! unary  = ("+" | "-") unary | power
+//! power  = atom (("^" | "**") unary)?
+//! atom   = number | "(" expr ")"
+//! number = decimal ("e" ("+" | "-")? digits)? | "0x" hex digits | "0b" binary digits
+//! ```
+//!
+//! Powers are right-associative and bind tighter than negation, so `-2^2` is `-4` and `2^-1` is `1/2`. Underscores can
+//! be used as digit separators, eg. `1_000_000`.
+
+use std::{iter::Peekable, str::CharIndices};
+
+use num_rational::Ratio;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero};
+
+use crate::errors::{HexError, HexResult};
+
+/// Evaluates `expression` to an exact ratio. Fails instead of rounding if any step doesn't fit in 64 bits.
+pub fn parse_expression(expression: &str) -> HexResult<Ratio<i64>> {
+    let mut parser = Parser { expression, chars: expression.char_indices().peekable() };
+    let value = parser.expr()?;
+    match parser.peek() {
+        Some((index, c)) => Err(parser.error(index, format!("unexpected `{c}`"))),
+        None => Ok(value),
+    }
+}
+
+struct Parser<'a> {
+    expression: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn expr(&mut self) -> HexResult<Ratio<i64>> {
+        let mut value = self.term()?;
+        loop {
+            value = if self.eat('+') {
+                value.checked_add(&self.term()?)
+            } else if self.eat('-') {
+                value.checked_sub(&self.term()?)
+            } else {
+                return Ok(value);
+            }
+            .ok_or(HexError::Overflow)?;
+        }
+    }
+
+    fn term(&mut self) -> HexResult<Ratio<i64>> {
+        let mut value = self.unary()?;
+        loop {
+            // check for ** before * so it isn't mistaken for multiplication
+            if self.peek_str("**") {
+                return Ok(value);
+            }
+            value = if self.eat('*') {
+                value.checked_mul(&self.unary()?).ok_or(HexError::Overflow)?
+            } else if let Some((index, _)) = self.peek().filter(|&(_, c)| c == '/') {
+                self.chars.next();
+                let divisor = self.unary()?;
+                if divisor.is_zero() {
+                    return Err(self.error(index, "division by zero".into()));
+                }
+                value.checked_div(&divisor).ok_or(HexError::Overflow)?
+            } else {
+                return Ok(value);
+            };
+        }
+    }
+
+    fn unary(&mut self) -> HexResult<Ratio<i64>> {
+        if self.eat('-') {
+            // checked_neg isn't implemented for Ratio
+            Ratio::zero().checked_sub(&self.unary()?).ok_or(HexError::Overflow)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> HexResult<Ratio<i64>> {
+        let base = self.atom()?;
+
+        let index = self.index();
+        if self.peek_str("**") {
+            self.chars.next();
+            self.chars.next();
+        } else if !self.eat('^') {
+            return Ok(base);
+        }
+
+        let exponent = self.unary()?;
+        if !exponent.is_integer() {
+            return Err(self.error(index, format!("exponent `{exponent}` is not an integer")));
+        }
+        if base.is_zero() && *exponent.numer() < 0 {
+            return Err(self.error(index, "division by zero".into()));
+        }
+        checked_pow(base, *exponent.numer()).ok_or(HexError::Overflow)
+    }
+
+    fn atom(&mut self) -> HexResult<Ratio<i64>> {
+        match self.peek() {
+            Some((_, '(')) => {
+                self.chars.next();
+                let value = self.expr()?;
+                if !self.eat(')') {
+                    let index = self.index();
+                    return Err(self.error(index, "expected `)`".into()));
+                }
+                Ok(value)
+            }
+            Some((_, c)) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some((index, c)) => Err(self.error(index, format!("unexpected `{c}`"))),
+            None => Err(self.error(self.expression.len(), "unexpected end of expression".into())),
+        }
+    }
+
+    fn number(&mut self) -> HexResult<Ratio<i64>> {
+        let start = self.index();
+
+        let radix = match self.expression[start..].get(..2) {
+            Some("0x" | "0X") => 16,
+            Some("0b" | "0B") => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.chars.nth(1);
+            let digits = self.take_while(|c| c.is_digit(radix));
+            return i64::from_str_radix(&digits, radix)
+                .map(Ratio::from_integer)
+                .map_err(|_| self.error(start, format!("invalid base {radix} integer")));
+        }
+
+        // parse 12.345e-6 as 12345 * 10^(-6 - 3)
+        let integer = self.take_while(|c| c.is_ascii_digit());
+        let fraction = match self.chars.next_if(|&(_, c)| c == '.') {
+            Some(_) => self.take_while(|c| c.is_ascii_digit()),
+            None => String::new(),
+        };
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(self.error(start, "expected a number".into()));
+        }
+
+        let mut exponent = 0;
+        if let Some((index, _)) = self.chars.next_if(|&(_, c)| c == 'e' || c == 'E') {
+            let sign = self.chars.next_if(|&(_, c)| c == '-' || c == '+').map(|(_, c)| c);
+            let digits = self.take_while(|c| c.is_ascii_digit());
+            exponent = digits.parse::<i64>().map_err(|_| self.error(index, "invalid exponent".into()))?;
+            if sign == Some('-') {
+                exponent = -exponent;
+            }
+        }
+
+        let exponent = exponent.checked_sub(fraction.len() as i64).ok_or(HexError::Overflow)?;
+        decimal(&format!("{integer}{fraction}"), exponent).ok_or(HexError::Overflow)
+    }
+
+    /// Takes characters matching `f` (or underscores) without skipping whitespace, since it's used inside numbers.
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let mut result = String::new();
+        while let Some((_, c)) = self.chars.next_if(|&(_, c)| f(c) || c == '_') {
+            if c != '_' {
+                result.push(c);
+            }
+        }
+        result
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<(usize, char)> {
+        self.skip_whitespace();
+        self.chars.peek().copied()
+    }
+
+    fn peek_str(&mut self, s: &str) -> bool {
+        self.peek().is_some_and(|(index, _)| self.expression[index..].starts_with(s))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.peek().is_some_and(|(_, next)| next == c) && self.chars.next().is_some()
+    }
+
+    fn index(&mut self) -> usize {
+        self.peek().map_or(self.expression.len(), |(index, _)| index)
+    }
+
+    fn error(&self, index: usize, message: String) -> HexError {
+        HexError::InvalidExpression(self.expression.to_string(), index, message)
+    }
+}
+
+/// Returns `digits * 10^exponent`, cancelling out zeros and common factors first so long mantissas like
+/// `0.1000000000000000000000` or `0.0000000000000000005` still fit. Returns `None` on overflow.
+fn decimal(digits: &str, exponent: i64) -> Option<Ratio<i64>> {
+    let trimmed = digits.trim_end_matches('0');
+    let exponent = exponent.checked_add((digits.len() - trimmed.len()) as i64)?;
+    let trimmed = trimmed.trim_start_matches('0');
+    if trimmed.is_empty() {
+        return Some(Ratio::zero());
+    }
+
+    let mut mantissa: u128 = trimmed.parse().ok()?;
+    if exponent >= 0 {
+        let mantissa = i64::try_from(mantissa).ok()?;
+        return Ratio::from_integer(mantissa).checked_mul(&checked_pow(10.into(), exponent)?);
+    }
+
+    // 10^-exponent = 2^-exponent * 5^-exponent, so divide out any of those factors the mantissa shares
+    let (mut twos, mut fives) = (exponent.unsigned_abs(), exponent.unsigned_abs());
+    while twos > 0 && mantissa % 2 == 0 {
+        mantissa /= 2;
+        twos -= 1;
+    }
+    while fives > 0 && mantissa % 5 == 0 {
+        mantissa /= 5;
+        fives -= 1;
+    }
+    let denom = 2i64.checked_pow(twos.try_into().ok()?)?.checked_mul(5i64.checked_pow(fives.try_into().ok()?)?)?;
+    Some(Ratio::new(i64::try_from(mantissa).ok()?, denom))
+}
+
+/// Raises `base` to an integer power by squaring, returning `None` on overflow.
+fn checked_pow(base: Ratio<i64>, exponent: i64) -> Option<Ratio<i64>> {
+    let (mut base, mut exponent) = match exponent {
+        0.. => (base, exponent.unsigned_abs()),
+        _ => (Ratio::one().checked_div(&base)?, exponent.unsigned_abs()),
+    };
+
+    let mut result = Ratio::one();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result.checked_mul(&base)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = base.checked_mul(&base)?;
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> Ratio<i64> {
+        parse_expression(expression).unwrap()
+    }
+
+    #[test]
+    fn follows_precedence() {
+        assert_eq!(eval("2+3*4"), 14.into());
+        assert_eq!(eval("(2+3)*4"), 20.into());
+        assert_eq!(eval("10-4-3"), 3.into());
+        assert_eq!(eval("12/4/3"), 1.into());
+        assert_eq!(eval("1 + 2 * 3 ^ 2"), 19.into());
+        assert_eq!(eval("6/4"), Ratio::new(3, 2));
+    }
+
+    #[test]
+    fn parses_number_formats() {
+        assert_eq!(eval("0x1F"), 31.into());
+        assert_eq!(eval("0Xff"), 255.into());
+        assert_eq!(eval("0b101"), 5.into());
+        assert_eq!(eval("1_000_000"), 1_000_000.into());
+        assert_eq!(eval("1.25"), Ratio::new(5, 4));
+        assert_eq!(eval(".5"), Ratio::new(1, 2));
+        assert_eq!(eval("1.5e3"), 1500.into());
+        assert_eq!(eval("25E-2"), Ratio::new(1, 4));
+    }
+
+    #[test]
+    fn powers_are_right_associative() {
+        assert_eq!(eval("2^20+3"), 1_048_579.into());
+        assert_eq!(eval("2**10"), 1024.into());
+        assert_eq!(eval("2^3^2"), 512.into());
+        assert_eq!(eval("2**-1"), Ratio::new(1, 2));
+        assert_eq!(eval("(1/2)^-3"), 8.into());
+        assert_eq!(eval("3*2**2"), 12.into());
+    }
+
+    #[test]
+    fn unary_minus_binds_looser_than_powers() {
+        assert_eq!(eval("-2^2"), (-4).into());
+        assert_eq!(eval("(-2)^2"), 4.into());
+        assert_eq!(eval("-0x1F/4"), Ratio::new(-31, 4));
+        assert_eq!(eval("--3"), 3.into());
+        assert_eq!(eval("-(3)"), (-3).into());
+        assert_eq!(eval("+-+1"), (-1).into());
+    }
+
+    #[test]
+    fn reduces_long_decimals() {
+        assert_eq!(eval("0.1000000000000000000000"), Ratio::new(1, 10));
+        assert_eq!(eval("0.0000000000000000005"), Ratio::new(1, 2_000_000_000_000_000_000));
+        assert_eq!(eval("100000000000000000000e-19"), 10.into());
+        assert_eq!(eval("000000000000000000000000001"), 1.into());
+        assert_eq!(eval("0.000"), 0.into());
+    }
+
+    #[test]
+    fn reports_overflow() {
+        for expression in [
+            "0.1000000000000000000001",
+            "9223372036854775808",
+            "1e19",
+            "1e-19",
+            "2^63",
+            "4611686018427387904*2",
+            "-9223372036854775807-2",
+        ] {
+            assert!(matches!(parse_expression(expression), Err(HexError::Overflow)), "{expression}");
+        }
+        assert_eq!(eval("2^62"), (1i64 << 62).into());
+    }
+
+    #[test]
+    fn reports_invalid_expressions() {
+        let error_at = |expression| match parse_expression(expression) {
+            Err(HexError::InvalidExpression(_, index, message)) => (index, message),
+            other => panic!("{expression}: {other:?}"),
+        };
+        assert_eq!(error_at("1/0"), (1, "division by zero".into()));
+        assert_eq!(error_at("0^-1"), (1, "division by zero".into()));
+        assert_eq!(error_at("2^(1/2)"), (1, "exponent `1/2` is not an integer".into()));
+        assert_eq!(error_at("(1+2"), (4, "expected `)`".into()));
+        assert_eq!(error_at("1 2"), (2, "unexpected `2`".into()));
+        assert_eq!(error_at("1+"), (2, "unexpected end of expression".into()));
+        assert_eq!(error_at("0xg"), (0, "invalid base 16 integer".into()));
+        assert_eq!(error_at("0x8000000000000000"), (0, "invalid base 16 integer".into()));
+        assert_eq!(error_at("1e"), (1, "invalid exponent".into()));
+    }
+}
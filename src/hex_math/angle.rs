@@ -1,5 +1,6 @@
 use crate::errors::{HexError, HexResult};
 use num_rational::Ratio;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul};
 use strum::EnumIter;
 
 #[derive(Debug, Hash, Clone, Copy, PartialEq, Eq, EnumIter)]
@@ -14,14 +15,16 @@ pub enum Angle {
 
 impl Angle {
     pub fn apply_to(&self, num: Ratio<u64>) -> HexResult<Ratio<u64>> {
+        // large targets and precise fractions can get close to the limits of u64, so don't assume this will fit
         match self {
-            Angle::Forward => Ok(num + 1),
-            Angle::Left => Ok(num + 5),
-            Angle::Right => Ok(num + 10),
-            Angle::LeftBack => Ok(num * 2),
-            Angle::RightBack => Ok(num / 2),
-            _ => Err(HexError::InvalidAngle(*self)),
+            Angle::Forward => num.checked_add(&1.into()),
+            Angle::Left => num.checked_add(&5.into()),
+            Angle::Right => num.checked_add(&10.into()),
+            Angle::LeftBack => num.checked_mul(&2.into()),
+            Angle::RightBack => num.checked_div(&2.into()),
+            _ => return Err(HexError::InvalidAngle(*self)),
         }
+        .ok_or(HexError::Overflow)
     }
 }
 
@@ -1,8 +1,13 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::HexError;
 
 use super::Angle;
 
-#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Direction {
     NorthEast = 0,
     East = 1,
@@ -53,3 +58,19 @@ impl Display for Direction {
         write!(f, "{}", name)
     }
 }
+
+impl FromStr for Direction {
+    type Err = HexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NORTH_EAST" => Ok(Direction::NorthEast),
+            "EAST" => Ok(Direction::East),
+            "SOUTH_EAST" => Ok(Direction::SouthEast),
+            "SOUTH_WEST" => Ok(Direction::SouthWest),
+            "WEST" => Ok(Direction::West),
+            "NORTH_WEST" => Ok(Direction::NorthWest),
+            _ => Err(HexError::InvalidDirection(s.to_string())),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Formats patterns the way Hex Casting and its tooling expect them.
+
+use clap::ValueEnum;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::HexResult,
+    hex_math::{Angle, Direction},
+};
+
+/// Which item a `/give` command should create.
+#[pyclass(module = "hexnumgen")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GiveItem {
+    /// A focus holding the pattern
+    #[default]
+    Focus,
+    /// A spellbook with the pattern on its first page
+    Spellbook,
+}
+
+/// Formats a pattern as a line of `.hexpattern` source, eg. `<south_east,aqaaeaqaa>`.
+pub fn hexpattern(direction: Direction, pattern: &str) -> String {
+    format!("<{},{pattern}>", direction.to_string().to_lowercase())
+}
+
+/// Formats a pattern as pattern iota SNBT, eg.
+/// `{"hexcasting:type":"hexcasting:pattern","hexcasting:data":{angles:[B;4b,5b,4b,4b],start_dir:2b}}`.
+///
+/// The angle and direction ordinals are the same as Hex Casting's `HexAngle` and `HexDir`.
+pub fn pattern_iota(direction: Direction, pattern: &str) -> HexResult<String> {
+    let angles = pattern
+        .chars()
+        .map(|c| Ok(format!("{}b", Angle::try_from(c)? as u8)))
+        .collect::<HexResult<Vec<_>>>()?
+        .join(",");
+
+    Ok(format!(
+        r#"{{"hexcasting:type":"hexcasting:pattern","hexcasting:data":{{angles:[B;{angles}],start_dir:{}b}}}}"#,
+        direction as u8
+    ))
+}
+
+/// Returns a `/give` command for an item holding `iota`, which should be SNBT from eg. [`pattern_iota`].
+pub fn give_command(item: GiveItem, iota: &str) -> String {
+    match item {
+        GiveItem::Focus => format!("/give @p hexcasting:focus{{data:{iota}}}"),
+        GiveItem::Spellbook => format!(r#"/give @p hexcasting:spellbook{{pages:{{"1":{iota}}},page_idx:1}}"#),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::HexError;
+
+    #[test]
+    fn hexpattern_uses_lowercase_direction() {
+        assert_eq!(hexpattern(Direction::SouthEast, "aqaaeaqaa"), "<south_east,aqaaeaqaa>");
+        assert_eq!(hexpattern(Direction::NorthWest, "dedd"), "<north_west,dedd>");
+    }
+
+    #[test]
+    fn pattern_iota_uses_hex_casting_ordinals() {
+        assert_eq!(
+            pattern_iota(Direction::SouthEast, "aqaa").unwrap(),
+            r#"{"hexcasting:type":"hexcasting:pattern","hexcasting:data":{angles:[B;4b,5b,4b,4b],start_dir:2b}}"#
+        );
+        assert_eq!(
+            pattern_iota(Direction::NorthEast, "wedsaq").unwrap(),
+            r#"{"hexcasting:type":"hexcasting:pattern","hexcasting:data":{angles:[B;0b,1b,2b,3b,4b,5b],start_dir:0b}}"#
+        );
+    }
+
+    #[test]
+    fn pattern_iota_rejects_invalid_angles() {
+        assert!(matches!(pattern_iota(Direction::East, "aqax"), Err(HexError::InvalidChar('x'))));
+    }
+
+    #[test]
+    fn give_command_wraps_iota() {
+        assert_eq!(give_command(GiveItem::Focus, "{}"), "/give @p hexcasting:focus{data:{}}");
+        assert_eq!(
+            give_command(GiveItem::Spellbook, "{}"),
+            r#"/give @p hexcasting:spellbook{pages:{"1":{}},page_idx:1}"#
+        );
+    }
+}
@@ -1,28 +1,53 @@
 #![feature(let_chains)]
 
+mod batch;
 mod errors;
+mod expr;
 mod hex_math;
+mod hexcasting;
 mod numgen;
+mod pickle;
+pub mod ratio_serde;
+mod render;
+mod request;
+mod signals;
+mod table;
 mod threadpool;
 mod traits;
 mod utils;
 
 use clap::Subcommand;
 use num_rational::Ratio;
-use numgen::{
-    generators::{
-        traits::PathGenerator, AStarParallelSplitPathGenerator, AStarPathGenerator, BeamParallelPoolPathGenerator,
-        BeamParallelSplitPathGenerator, BeamPathGenerator,
-    },
-    Path,
+use num_traits::Signed;
+use numgen::generators::{
+    AStarParallelSplitPathGenerator, AStarPathGenerator, BeamParallelPoolPathGenerator, BeamParallelSplitPathGenerator,
+    BeamPathGenerator,
 };
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use traits::{DyadicRatio, UnsignedAbsRatio};
+use utils::{approximate_dyadic, exact_ratio};
 
-pub use hex_math::Direction;
+pub use batch::{for_each_parallel, generate_batch, generate_batch_with_hooks, in_order};
+pub use errors::{HexError, HexResult};
+pub use expr::parse_expression;
+pub use hex_math::{Angle, Direction};
+pub use hexcasting::{give_command, hexpattern, pattern_iota, GiveItem};
+// the API for writing generators: the search traits, the types in their signatures, and the registry to add them to
 pub use numgen::{
-    generators::{AStarOptions, AStarSplitOptions, BeamOptions, BeamPoolOptions, BeamSplitOptions},
-    Bounds,
+    generators::{
+        traits::{AStar, BeamSearch, PathGenerator, Split},
+        AStarOptions, AStarSplitOptions, BeamOptions, BeamPoolOptions, BeamSplitOptions, GeneratorFactory,
+        GeneratorRegistry, NamedOptions,
+    },
+    Bounds, Ceiling, FractionPolicy, Objective, Path, PathLimits, QueuedPath, SearchHooks, SharedPath,
 };
+pub use render::render_pattern;
+pub use request::{GenerationRequest, LimitArgs};
+pub use table::{
+    write_table, NumberTable, TableEntry, TableFormat, TableGenerator, TableMetadata, TableMetrics, TableWriteOptions,
+};
+pub use utils::NonZeroSign;
 
 #[derive(FromPyObject)]
 pub enum PyRatio {
@@ -30,29 +55,162 @@ pub enum PyRatio {
     Int(i64),
     #[pyo3(annotation = "tuple[int, int]")]
     Tuple(i64, i64),
+    #[pyo3(annotation = "float")]
+    Float(f64),
+    #[pyo3(annotation = "str")]
+    Expression(String),
+}
+
+impl TryFrom<PyRatio> for Ratio<i64> {
+    type Error = HexError;
+
+    fn try_from(value: PyRatio) -> HexResult<Self> {
+        match value {
+            PyRatio::Int(n) => Ok(n.into()),
+            PyRatio::Tuple(numer, denom) => Ok(Ratio::new(numer, denom)),
+            PyRatio::Float(value) => float_target(value),
+            PyRatio::Expression(expression) => parse_expression(&expression),
+        }
+    }
+}
+
+#[derive(FromPyObject)]
+pub enum PyCeiling {
+    #[pyo3(annotation = "bool")]
+    Bool(bool),
+    #[pyo3(annotation = "dict[str, int | float | tuple[int, int]]")]
+    Multiple {
+        #[pyo3(item)]
+        multiple: PyRatio,
+    },
+    #[pyo3(annotation = "dict[str, int | float | tuple[int, int]]")]
+    Absolute {
+        #[pyo3(item)]
+        absolute: PyRatio,
+    },
 }
 
-impl From<PyRatio> for Ratio<i64> {
-    fn from(value: PyRatio) -> Self {
+impl TryFrom<PyCeiling> for Ceiling {
+    type Error = HexError;
+
+    fn try_from(value: PyCeiling) -> HexResult<Self> {
+        Ok(match value {
+            PyCeiling::Bool(trim_larger) => trim_larger.into(),
+            PyCeiling::Multiple { multiple } => Ceiling::Multiple(non_negative(multiple.try_into()?)?),
+            PyCeiling::Absolute { absolute } => Ceiling::Absolute(non_negative(absolute.try_into()?)?),
+        })
+    }
+}
+
+#[derive(FromPyObject)]
+pub enum PyFractionPolicy {
+    #[pyo3(annotation = "bool")]
+    Bool(bool),
+    #[pyo3(annotation = "FractionPolicy")]
+    Policy(FractionPolicy),
+}
+
+impl From<PyFractionPolicy> for FractionPolicy {
+    fn from(value: PyFractionPolicy) -> Self {
         match value {
-            PyRatio::Int(n) => n.into(),
-            PyRatio::Tuple(numer, denom) => Ratio::new(numer, denom),
+            PyFractionPolicy::Bool(allow_fractions) => allow_fractions.into(),
+            PyFractionPolicy::Policy(policy) => policy,
         }
     }
 }
 
-#[derive(FromPyObject, Subcommand)]
+#[derive(Debug, Clone, FromPyObject, Subcommand, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "kebab-case")]
 pub enum GeneratorOptions {
     Beam(BeamOptions),
     BeamPool(BeamPoolOptions),
     BeamSplit(BeamSplitOptions),
     #[command(name = "astar")]
+    #[serde(rename = "astar")]
     AStar(AStarOptions),
     #[command(name = "astar-split")]
+    #[serde(rename = "astar-split")]
     AStarSplit(AStarSplitOptions),
+    /// Any generator in the registry, including ones added by other crates
+    #[serde(untagged)]
+    Named(NamedOptions),
+}
+
+impl GeneratorOptions {
+    /// Checks for options that would make the generator panic or never find anything.
+    pub fn validate(&self) -> HexResult<()> {
+        let (bounds, carryover, num_threads) = match *self {
+            Self::Beam(BeamOptions { bounds, carryover }) => (Some(bounds), Some(carryover), None),
+            Self::BeamPool(BeamPoolOptions { bounds, carryover, num_threads })
+            | Self::BeamSplit(BeamSplitOptions { bounds, carryover, num_threads }) => {
+                (Some(bounds), Some(carryover), Some(num_threads))
+            }
+            Self::AStar(_) => (None, None, None),
+            Self::AStarSplit(AStarSplitOptions { num_threads }) => (None, None, Some(num_threads)),
+            Self::Named(NamedOptions { ref name, .. }) => {
+                // the generator itself checks its parameters when it's built
+                if !GeneratorRegistry::global().read().contains(name) {
+                    return Err(HexError::UnknownGenerator(name.clone()));
+                }
+                (None, None, None)
+            }
+        };
+
+        if num_threads == Some(0) {
+            return Err(HexError::InvalidOptions("num_threads must be at least 1".into()));
+        }
+        if carryover == Some(0) {
+            return Err(HexError::InvalidOptions("carryover must be at least 1".into()));
+        }
+        if bounds.is_some_and(|Bounds { q, r, s }| q == 0 || r == 0 || s == 0) {
+            return Err(HexError::InvalidOptions("bounds must be at least 1 in every dimension".into()));
+        }
+        Ok(())
+    }
+
+    /// The name the generator is registered and serialized under, eg. `astar-split`.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Beam(_) => "beam",
+            Self::BeamPool(_) => "beam-pool",
+            Self::BeamSplit(_) => "beam-split",
+            Self::AStar(_) => "astar",
+            Self::AStarSplit(_) => "astar-split",
+            Self::Named(NamedOptions { name, .. }) => name,
+        }
+    }
+
+    /// How many threads the generator will use.
+    pub fn num_threads(&self) -> usize {
+        match self {
+            Self::Beam(_) | Self::AStar(_) => 1,
+            Self::BeamPool(BeamPoolOptions { num_threads, .. })
+            | Self::BeamSplit(BeamSplitOptions { num_threads, .. })
+            | Self::AStarSplit(AStarSplitOptions { num_threads }) => *num_threads,
+            Self::Named(NamedOptions { params, .. }) => {
+                params.get("num_threads").and_then(|n| n.as_u64()).map_or(1, |n| n as usize)
+            }
+        }
+    }
+
+    pub fn run(self, target: Ratio<i64>, limits: PathLimits) -> HexResult<Option<Path>> {
+        Ok(match self {
+            Self::Beam(opts) => PathGenerator::run(BeamPathGenerator::new(target, limits, opts)),
+            Self::BeamPool(opts) => PathGenerator::run(BeamParallelPoolPathGenerator::new(target, limits, opts)),
+            Self::BeamSplit(opts) => PathGenerator::run(BeamParallelSplitPathGenerator::new(target, limits, opts)),
+            Self::AStar(opts) => PathGenerator::run(AStarPathGenerator::new(target, limits, opts)),
+            Self::AStarSplit(opts) => PathGenerator::run(AStarParallelSplitPathGenerator::new(target, limits, opts)),
+            Self::Named(NamedOptions { name, params }) => {
+                // don't hold the lock while searching, so generators can be registered in the meantime
+                let factory = GeneratorRegistry::global().read().get(&name)?;
+                return factory(target, limits, params.into());
+            }
+        })
+    }
 }
 
-#[pyclass(get_all)]
+#[pyclass(module = "hexnumgen", get_all)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeneratedNumber {
     pub direction: String,
     pub pattern: String,
@@ -61,10 +219,49 @@ pub struct GeneratedNumber {
     pub num_segments: usize,
 }
 
-#[pymethods]
+pickle::pymethods! {
+    impl GeneratedNumber {
+        fn __str__(&self) -> String {
+            format!("{} {}", self.direction, self.pattern)
+        }
+
+        #[pyo3(name = "hexpattern")]
+        fn hexpattern_py(&self) -> PyResult<String> {
+            Ok(self.hexpattern()?)
+        }
+
+        #[pyo3(name = "iota")]
+        fn iota_py(&self) -> PyResult<String> {
+            Ok(self.iota()?)
+        }
+
+        #[pyo3(name = "give_command", signature = (item=GiveItem::Focus))]
+        fn give_command_py(&self, item: GiveItem) -> PyResult<String> {
+            Ok(self.give_command(item)?)
+        }
+    }
+}
+
+impl pickle::JsonPickle for GeneratedNumber {}
+
 impl GeneratedNumber {
-    fn __str__(&self) -> String {
-        format!("{} {}", self.direction, self.pattern)
+    pub fn starting_direction(&self) -> HexResult<Direction> {
+        self.direction.parse()
+    }
+
+    /// Formats the pattern as a line of `.hexpattern` source.
+    pub fn hexpattern(&self) -> HexResult<String> {
+        Ok(hexpattern(self.starting_direction()?, &self.pattern))
+    }
+
+    /// Formats the pattern as pattern iota SNBT.
+    pub fn iota(&self) -> HexResult<String> {
+        pattern_iota(self.starting_direction()?, &self.pattern)
+    }
+
+    /// Returns a `/give` command for an item holding the pattern.
+    pub fn give_command(&self, item: GiveItem) -> HexResult<String> {
+        Ok(give_command(item, &self.iota()?))
     }
 }
 
@@ -80,49 +277,344 @@ impl From<Path> for GeneratedNumber {
     }
 }
 
-pub fn generate_number_pattern(
-    target: Ratio<i64>,
-    trim_larger: bool,
-    allow_fractions: bool,
-    options: GeneratorOptions,
-) -> Option<GeneratedNumber> {
-    // TODO: fix these types. ew
-    match options {
-        GeneratorOptions::Beam(opts) => BeamPathGenerator::new(target, trim_larger, allow_fractions, opts).run(),
-        GeneratorOptions::BeamPool(opts) => {
-            BeamParallelPoolPathGenerator::new(target, trim_larger, allow_fractions, opts).run()
-        }
-        GeneratorOptions::BeamSplit(opts) => {
-            BeamParallelSplitPathGenerator::new(target, trim_larger, allow_fractions, opts).run()
+#[pyclass(module = "hexnumgen", get_all)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproximatedNumber {
+    pub numerator: i64,
+    pub denominator: i64,
+    pub number: GeneratedNumber,
+}
+
+pickle::pymethods! {
+    impl ApproximatedNumber {
+        fn __str__(&self) -> String {
+            format!("{} ({})", self.number.__str__(), self.value())
         }
-        GeneratorOptions::AStar(opts) => AStarPathGenerator::new(target, trim_larger, allow_fractions, opts).run(),
-        GeneratorOptions::AStarSplit(opts) => {
-            AStarParallelSplitPathGenerator::new(target, trim_larger, allow_fractions, opts).run()
+    }
+}
+
+impl pickle::JsonPickle for ApproximatedNumber {}
+
+impl ApproximatedNumber {
+    pub fn value(&self) -> Ratio<i64> {
+        Ratio::new(self.numerator, self.denominator)
+    }
+}
+
+/// Converts `value` to the exact number it represents, eg. `0.1` is `3602879701896397/36028797018963968`, since that's
+/// what Hex Casting would actually hold.
+pub fn float_target(value: f64) -> HexResult<Ratio<i64>> {
+    exact_ratio(value).ok_or(HexError::UnrepresentableFloat(value))
+}
+
+/// Converts a ratio to an unsigned ratio, for values like ceilings where negative numbers don't make sense.
+pub fn non_negative(value: Ratio<i64>) -> HexResult<Ratio<u64>> {
+    if value.is_negative() {
+        return Err(HexError::Negative(value));
+    }
+    Ok(value.unsigned_abs())
+}
+
+/// Checks that it's possible to generate a pattern for `target` before starting a search that would never finish.
+pub fn check_target(target: Ratio<i64>, ceiling: Ceiling, fractions: FractionPolicy) -> HexResult<()> {
+    if let Some(max_value) = ceiling.max_value(target.unsigned_abs()) {
+        if max_value < target.unsigned_abs() {
+            return Err(HexError::CeilingBelowTarget(target));
+        } else if let Some(start) = Ceiling::halving_start(target.unsigned_abs())
+            && max_value < start
+        {
+            return Err(HexError::CeilingBelowHalvingStart(target, start));
         }
     }
-    .map(Into::into)
+
+    if target.is_integer() {
+        return Ok(());
+    } else if !fractions.fractional_target {
+        return Err(HexError::FractionalTarget(target));
+    } else if !target.is_dyadic() {
+        // halving is the only way to make a fraction, so the denominator must be a power of two
+        return Err(HexError::UnreachableTarget(target));
+    }
+
+    // the value right before the final halving has half of the target's denominator
+    let needed_denom = target.denom().unsigned_abs() / 2;
+    if needed_denom > 1 && !fractions.allows_intermediate(Ratio::new(1, needed_denom)) {
+        return Err(HexError::IntermediateFractionsRequired(target, needed_denom));
+    }
+    Ok(())
+}
+
+pub fn generate_number_pattern(
+    target: Ratio<i64>,
+    ceiling: Ceiling,
+    fractions: FractionPolicy,
+    options: GeneratorOptions,
+) -> HexResult<Option<GeneratedNumber>> {
+    GenerationRequest::new(target, options).with_ceiling(ceiling).with_fractions(fractions).generate()
+}
+
+/// Generates a pattern for the simplest value within `tolerance` of `value` that `fractions` allows as a target.
+pub fn approximate_number_pattern(
+    value: f64,
+    tolerance: f64,
+    ceiling: Ceiling,
+    fractions: FractionPolicy,
+    objective: Objective,
+    options: GeneratorOptions,
+) -> HexResult<Option<ApproximatedNumber>> {
+    approximate_with_hooks(value, tolerance, ceiling, fractions, objective, options, SearchHooks::default())
+}
+
+fn approximate_with_hooks(
+    value: f64,
+    tolerance: f64,
+    ceiling: Ceiling,
+    fractions: FractionPolicy,
+    objective: Objective,
+    options: GeneratorOptions,
+    hooks: SearchHooks,
+) -> HexResult<Option<ApproximatedNumber>> {
+    let target = approximate_dyadic(value, tolerance, fractions.max_target_denominator())
+        .ok_or(HexError::NoApproximation(value, tolerance))?;
+    let request = GenerationRequest::new(target, options)
+        .with_ceiling(ceiling)
+        .with_fractions(fractions)
+        .with_objective(objective);
+
+    Ok(request.generate_with_hooks(hooks)?.map(|number| ApproximatedNumber {
+        numerator: *target.numer(),
+        denominator: *target.denom(),
+        number,
+    }))
 }
 
 #[pyfunction]
 #[pyo3(name = "generate_number_pattern")]
 fn generate_number_pattern_py(
+    py: Python,
     target: PyRatio,
-    trim_larger: bool,
-    allow_fractions: bool,
+    ceiling: PyCeiling,
+    allow_fractions: PyFractionPolicy,
+    options: GeneratorOptions,
+) -> PyResult<Option<GeneratedNumber>> {
+    let request = GenerationRequest::new(target.try_into()?, options)
+        .with_ceiling(ceiling.try_into()?)
+        .with_fractions(allow_fractions.into());
+    Ok(signals::allow_threads(py, |hooks| request.generate_with_hooks(hooks))??)
+}
+
+#[pyfunction]
+#[pyo3(name = "approximate_number_pattern", signature = (value, tolerance, ceiling, options, fractions=None, objective=None))]
+fn approximate_number_pattern_py(
+    py: Python,
+    value: f64,
+    tolerance: f64,
+    ceiling: PyCeiling,
     options: GeneratorOptions,
-) -> Option<GeneratedNumber> {
-    generate_number_pattern(target.into(), trim_larger, allow_fractions, options)
+    fractions: Option<PyFractionPolicy>,
+    objective: Option<Objective>,
+) -> PyResult<Option<ApproximatedNumber>> {
+    let ceiling = ceiling.try_into()?;
+    // approximations have always been allowed to be fractions
+    let fractions = fractions.map_or(FractionPolicy::from(true), Into::into);
+    let objective = objective.unwrap_or_default();
+    Ok(signals::allow_threads(py, |hooks| {
+        approximate_with_hooks(value, tolerance, ceiling, fractions, objective, options, hooks)
+    })??)
+}
+
+/// Evaluates an expression like `2^20+3` or `-0x1F/4`, returning `(numerator, denominator)`.
+#[pyfunction]
+#[pyo3(name = "parse_expression")]
+fn parse_expression_py(expression: &str) -> PyResult<(i64, i64)> {
+    let value = parse_expression(expression)?;
+    Ok((*value.numer(), *value.denom()))
+}
+
+/// Returns the names of every generator that can be used with `NamedOptions`.
+#[pyfunction]
+fn generator_names() -> Vec<String> {
+    GeneratorRegistry::global().read().names()
 }
 
 #[pymodule]
 fn hexnumgen(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(generate_number_pattern_py, m)?)?;
+    m.add_function(wrap_pyfunction!(approximate_number_pattern_py, m)?)?;
+    m.add_function(wrap_pyfunction!(batch::generate_many, m)?)?;
+    m.add_function(wrap_pyfunction!(generator_names, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_expression_py, m)?)?;
     m.add_class::<GeneratedNumber>()?;
+    m.add_class::<ApproximatedNumber>()?;
+    m.add_class::<GiveItem>()?;
     m.add_class::<Bounds>()?;
+    m.add_class::<FractionPolicy>()?;
+    m.add_class::<Objective>()?;
+    m.add_class::<GenerationRequest>()?;
+    m.add_class::<NumberTable>()?;
+    m.add_class::<batch::GenerationResult>()?;
+    m.add_class::<batch::GenerationResults>()?;
     m.add_class::<BeamOptions>()?;
     m.add_class::<BeamPoolOptions>()?;
     m.add_class::<BeamSplitOptions>()?;
     m.add_class::<AStarOptions>()?;
     m.add_class::<AStarSplitOptions>()?;
+    m.add_class::<NamedOptions>()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_of(number: &GeneratedNumber) -> Ratio<u64> {
+        numgen::Path::from_pattern(number.starting_direction().unwrap(), &number.pattern).unwrap().value()
+    }
+
+    #[test]
+    fn generates_exact_floats() {
+        let request = |value: f64, algorithm: GeneratorOptions| {
+            GenerationRequest::new(float_target(value).unwrap(), algorithm)
+                .with_ceiling(Ceiling::Unbounded)
+                .with_fractions(true.into())
+        };
+        let number = request(0.375, GeneratorOptions::AStar(AStarOptions {})).generate().unwrap().unwrap();
+        assert_eq!(value_of(&number), Ratio::new(3, 8));
+
+        // 0.1 needs 55 halvings, which is too deep for the searches to find, but they still finish
+        let beam = GeneratorOptions::Beam(BeamOptions { bounds: Bounds::new(8, 8, 8), carryover: 5 });
+        assert!(request(0.1, beam).generate().unwrap().is_none());
+    }
+
+    #[test]
+    fn check_target_rejects_unreachable_fractions() {
+        let fractions = FractionPolicy::from(true);
+        assert!(check_target(Ratio::new(3, 8), Ceiling::Unbounded, fractions).is_ok());
+        assert!(check_target(Ratio::new(-7, 2), Ceiling::default(), fractions).is_ok());
+        assert!(matches!(
+            check_target(Ratio::new(1, 3), Ceiling::Unbounded, fractions),
+            Err(HexError::UnreachableTarget(_))
+        ));
+        assert!(matches!(
+            check_target(Ratio::new(5, 6), Ceiling::Unbounded, fractions),
+            Err(HexError::UnreachableTarget(_))
+        ));
+    }
+
+    #[test]
+    fn check_target_follows_fraction_policy() {
+        let half = Ratio::new(1, 2);
+        assert!(matches!(
+            check_target(half, Ceiling::Unbounded, FractionPolicy::default()),
+            Err(HexError::FractionalTarget(_))
+        ));
+        // only the final halving makes a fraction, so 1/2 doesn't need fractional intermediates
+        assert!(check_target(half, Ceiling::Unbounded, FractionPolicy::new(true, false, None)).is_ok());
+
+        let target = Ratio::new(5, 8);
+        assert!(matches!(
+            check_target(target, Ceiling::Unbounded, FractionPolicy::new(true, false, None)),
+            Err(HexError::IntermediateFractionsRequired(_, 4))
+        ));
+        assert!(matches!(
+            check_target(target, Ceiling::Unbounded, FractionPolicy::new(true, true, Some(2))),
+            Err(HexError::IntermediateFractionsRequired(_, 4))
+        ));
+        assert!(check_target(target, Ceiling::Unbounded, FractionPolicy::new(true, true, Some(4))).is_ok());
+    }
+
+    #[test]
+    fn fraction_policy_limits_generated_patterns() {
+        let request = GenerationRequest::new(Ratio::new(3, 4), GeneratorOptions::AStar(AStarOptions {}))
+            .with_ceiling(Ceiling::Unbounded)
+            .with_fractions(FractionPolicy::new(true, true, Some(2)));
+        let number = request.generate().unwrap().unwrap();
+        assert_eq!(value_of(&number), Ratio::new(3, 4));
+    }
+
+    #[test]
+    fn ceiling_is_checked_against_the_target() {
+        assert!(matches!(
+            check_target(100.into(), Ceiling::Absolute(50.into()), FractionPolicy::default()),
+            Err(HexError::CeilingBelowTarget(_))
+        ));
+        assert!(matches!(
+            check_target(Ratio::new(1, 4), Ceiling::Absolute(Ratio::new(1, 2)), true.into()),
+            Err(HexError::CeilingBelowHalvingStart(_, start)) if start == 1.into()
+        ));
+        assert!(matches!(
+            check_target(Ratio::new(3, 8), Ceiling::Absolute(1.into()), true.into()),
+            Err(HexError::CeilingBelowHalvingStart(_, start)) if start == Ratio::new(3, 2)
+        ));
+        assert!(check_target(0.into(), Ceiling::Absolute(0.into()), FractionPolicy::default()).is_ok());
+        // multiples leave room for the value that fractions below 1 are halved down from
+        assert!(check_target(Ratio::new(3, 8), Ceiling::default(), true.into()).is_ok());
+    }
+
+    #[test]
+    fn paths_stay_under_the_ceiling() {
+        let values_along = |number: &GeneratedNumber| {
+            let mut value = Ratio::from_integer(0);
+            number.pattern[4..]
+                .chars()
+                .map(|c| {
+                    value = hex_math::Angle::try_from(c).unwrap().apply_to(value).unwrap();
+                    value
+                })
+                .collect::<Vec<_>>()
+        };
+        let beam = GeneratorOptions::Beam(BeamOptions { bounds: Bounds::default(), carryover: 25 });
+
+        for options in [beam, GeneratorOptions::AStar(AStarOptions {})] {
+            for (target, ceiling, max_value) in [
+                (Ratio::from_integer(100), Ceiling::default(), Ratio::from_integer(100)),
+                (Ratio::from_integer(100), Ceiling::Absolute(120.into()), Ratio::from_integer(120)),
+                (Ratio::new(3, 4), Ceiling::default(), Ratio::new(3, 2)),
+            ] {
+                let request = GenerationRequest::new(target, options.clone())
+                    .with_ceiling(ceiling)
+                    .with_fractions(FractionPolicy::from(true));
+                let number = request.generate().unwrap().unwrap();
+                let values = values_along(&number);
+                assert_eq!(values.last(), Some(&target.unsigned_abs()));
+                assert!(values.iter().all(|&value| value <= max_value), "{values:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn approximate_finds_nearby_dyadic_target() {
+        let approximate = |value, fractions| {
+            let options = GeneratorOptions::AStar(AStarOptions {});
+            approximate_number_pattern(value, 0.1, Ceiling::Unbounded, fractions, Objective::default(), options)
+        };
+        let number = approximate(0.3, true.into()).unwrap().unwrap();
+        assert_eq!(number.value(), Ratio::new(1, 4));
+        assert!(matches!(approximate(f64::NAN, true.into()), Err(HexError::NoApproximation(..))));
+    }
+
+    #[test]
+    fn approximate_follows_fraction_policy() {
+        let approximate = |value, fractions| {
+            let options = GeneratorOptions::AStar(AStarOptions {});
+            approximate_number_pattern(value, 0.3, Ceiling::Unbounded, fractions, Objective::Segments, options)
+        };
+        assert!(matches!(approximate(0.2, FractionPolicy::default()), Ok(Some(number)) if number.value() == 0.into()));
+        assert!(matches!(approximate(0.8, FractionPolicy::default()), Ok(Some(number)) if number.value() == 1.into()));
+        assert!(matches!(
+            approximate(0.5, FractionPolicy::new(true, false, None)),
+            Ok(Some(number)) if number.value() == Ratio::new(1, 2)
+        ));
+        assert!(matches!(approximate(0.5, FractionPolicy::default()), Err(HexError::NoApproximation(..))));
+    }
+
+    #[test]
+    fn generator_name_matches_serde_tag() {
+        for name in ["beam", "beam-pool", "beam-split", "astar", "astar-split", "not-builtin"] {
+            let options: GeneratorOptions =
+                serde_json::from_value(serde_json::json!({ "name": name, "num_threads": 2 })).unwrap();
+            assert_eq!(options.name(), name);
+            assert_eq!(serde_json::to_value(&options).unwrap()["name"], name);
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use clap::Args;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass(get_all, set_all)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Args)]
+use crate::pickle;
+
+#[pyclass(module = "hexnumgen", get_all, set_all)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Args, Serialize, Deserialize)]
 pub struct Bounds {
     #[arg(short, long = "q_size", default_value_t = 8)]
     pub q: u32,
@@ -12,24 +15,27 @@ pub struct Bounds {
     pub s: u32,
 }
 
-#[pymethods]
-impl Bounds {
-    #[new]
-    pub fn new(q: u32, r: u32, s: u32) -> Self {
-        Self { q, r, s }
-    }
+pickle::pymethods! {
+    impl Bounds {
+        #[new]
+        pub fn new(q: u32, r: u32, s: u32) -> Self {
+            Self { q, r, s }
+        }
 
-    #[getter]
-    pub fn largest_dimension(&self) -> u32 {
-        self.q.max(self.r).max(self.s)
-    }
+        #[getter]
+        pub fn largest_dimension(&self) -> u32 {
+            self.q.max(self.r).max(self.s)
+        }
 
-    #[getter]
-    pub fn quasi_area(&self) -> u32 {
-        self.q * self.r * self.s
+        #[getter]
+        pub fn quasi_area(&self) -> u32 {
+            self.q * self.r * self.s
+        }
     }
 }
 
+impl pickle::JsonPickle for Bounds {}
+
 impl Bounds {
     pub fn is_better_than(&self, other: Self) -> bool {
         self.quasi_area() < other.quasi_area()
@@ -40,6 +46,12 @@ impl Bounds {
     }
 }
 
+impl Default for Bounds {
+    fn default() -> Self {
+        Self::from(8)
+    }
+}
+
 impl From<u32> for Bounds {
     fn from(size: u32) -> Self {
         Self::new(size, size, size)
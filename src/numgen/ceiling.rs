@@ -0,0 +1,90 @@
+use num_rational::Ratio;
+use num_traits::{CheckedMul, Zero};
+use serde::{Deserialize, Serialize};
+
+/// Largest value that a path may pass through on the way to its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ceiling {
+    /// Values may grow without limit (paths generate slower but may be more compact).
+    Unbounded,
+    /// Values may be at most this multiple of the target, eg. `1` discards any path larger than the target. For targets
+    /// below 1, it's raised to the [halving start](Self::halving_start) if needed.
+    Multiple(#[serde(with = "crate::ratio_serde")] Ratio<u64>),
+    /// Values may be at most this large, regardless of the target.
+    Absolute(#[serde(with = "crate::ratio_serde")] Ratio<u64>),
+}
+
+impl Ceiling {
+    pub fn max_value(&self, target: Ratio<u64>) -> Option<Ratio<u64>> {
+        match *self {
+            Self::Unbounded => None,
+            // if this overflows, no value could ever reach the ceiling anyway
+            Self::Multiple(multiple) => target
+                .checked_mul(&multiple)
+                .map(|max_value| Self::halving_start(target).map_or(max_value, |start| max_value.max(start))),
+            Self::Absolute(max_value) => Some(max_value),
+        }
+    }
+
+    /// For targets between 0 and 1, the first doubling of the target that's at least 1. Every path to the target passes
+    /// through it, since adding always gives at least 1 and only halving can bring that back down.
+    pub fn halving_start(target: Ratio<u64>) -> Option<Ratio<u64>> {
+        if target.is_zero() || target >= 1.into() {
+            return None;
+        }
+
+        let mut start = target;
+        while start < 1.into() {
+            start *= 2;
+        }
+        Some(start)
+    }
+}
+
+impl Default for Ceiling {
+    fn default() -> Self {
+        Self::Multiple(1.into())
+    }
+}
+
+impl From<bool> for Ceiling {
+    fn from(trim_larger: bool) -> Self {
+        if trim_larger {
+            Self::default()
+        } else {
+            Self::Unbounded
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiple_scales_with_target() {
+        assert_eq!(Ceiling::default().max_value(40.into()), Some(40.into()));
+        assert_eq!(Ceiling::Multiple(Ratio::new(3, 2)).max_value(40.into()), Some(60.into()));
+        assert_eq!(Ceiling::Multiple(2.into()).max_value(u64::MAX.into()), None);
+        assert_eq!(Ceiling::Unbounded.max_value(40.into()), None);
+        assert_eq!(Ceiling::Absolute(8.into()).max_value(40.into()), Some(8.into()));
+    }
+
+    #[test]
+    fn halving_start_is_between_one_and_two() {
+        assert_eq!(Ceiling::halving_start(Ratio::new(1, 4)), Some(1.into()));
+        assert_eq!(Ceiling::halving_start(Ratio::new(3, 4)), Some(Ratio::new(3, 2)));
+        assert_eq!(Ceiling::halving_start(Ratio::new(3, 16)), Some(Ratio::new(3, 2)));
+        assert_eq!(Ceiling::halving_start(Ratio::new(3, 2)), None);
+        assert_eq!(Ceiling::halving_start(0.into()), None);
+    }
+
+    #[test]
+    fn multiple_leaves_room_for_fractions_below_one() {
+        assert_eq!(Ceiling::default().max_value(Ratio::new(1, 4)), Some(1.into()));
+        assert_eq!(Ceiling::default().max_value(Ratio::new(5, 8)), Some(Ratio::new(5, 4)));
+        assert_eq!(Ceiling::Multiple(4.into()).max_value(Ratio::new(5, 8)), Some(Ratio::new(5, 2)));
+        assert_eq!(Ceiling::Absolute(1.into()).max_value(Ratio::new(5, 8)), Some(1.into()));
+    }
+}
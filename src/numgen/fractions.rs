@@ -0,0 +1,92 @@
+use clap::Args;
+use num_rational::Ratio;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::pickle;
+
+#[pyclass(module = "hexnumgen", get_all, set_all)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Args, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FractionPolicy {
+    /// If the target may be a fraction
+    #[arg(long)]
+    pub fractional_target: bool,
+    /// If values along the way to the target may be fractions
+    #[arg(long)]
+    pub fractional_intermediates: bool,
+    /// Largest denominator allowed for values along the way to the target
+    #[arg(long)]
+    pub max_denominator: Option<u64>,
+}
+
+pickle::pymethods! {
+    impl FractionPolicy {
+        #[new]
+        #[pyo3(signature = (fractional_target=false, fractional_intermediates=false, max_denominator=None))]
+        pub fn new(fractional_target: bool, fractional_intermediates: bool, max_denominator: Option<u64>) -> Self {
+            Self { fractional_target, fractional_intermediates, max_denominator }
+        }
+    }
+}
+
+impl pickle::JsonPickle for FractionPolicy {}
+
+impl FractionPolicy {
+    pub fn allows_intermediate(&self, value: Ratio<u64>) -> bool {
+        value.is_integer()
+            || self.fractional_intermediates && self.max_denominator.map_or(true, |max| *value.denom() <= max)
+    }
+
+    /// Largest denominator a target can have under this policy, if it's limited. The target's denominator can be twice
+    /// that of the intermediates, since the last angle halves the value.
+    pub fn max_target_denominator(&self) -> Option<u64> {
+        if !self.fractional_target {
+            Some(1)
+        } else if !self.fractional_intermediates {
+            Some(2)
+        } else {
+            self.max_denominator.map(|max| max.saturating_mul(2))
+        }
+    }
+}
+
+impl From<bool> for FractionPolicy {
+    fn from(allow_fractions: bool) -> Self {
+        Self::new(allow_fractions, allow_fractions, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_are_always_allowed() {
+        let policy = FractionPolicy::default();
+        assert!(policy.allows_intermediate(Ratio::from_integer(12)));
+        assert!(!policy.allows_intermediate(Ratio::new(1, 2)));
+    }
+
+    #[test]
+    fn max_denominator_caps_intermediates() {
+        let policy = FractionPolicy::new(true, true, Some(4));
+        assert!(policy.allows_intermediate(Ratio::new(3, 4)));
+        assert!(!policy.allows_intermediate(Ratio::new(1, 8)));
+        assert!(FractionPolicy::new(false, true, None).allows_intermediate(Ratio::new(1, 1024)));
+    }
+
+    #[test]
+    fn target_denominator_allows_a_final_halving() {
+        assert_eq!(FractionPolicy::default().max_target_denominator(), Some(1));
+        assert_eq!(FractionPolicy::new(true, false, None).max_target_denominator(), Some(2));
+        assert_eq!(FractionPolicy::new(true, true, Some(8)).max_target_denominator(), Some(16));
+        assert_eq!(FractionPolicy::new(true, true, None).max_target_denominator(), None);
+    }
+
+    #[test]
+    fn bool_enables_everything() {
+        assert_eq!(FractionPolicy::from(true), FractionPolicy::new(true, true, None));
+        assert_eq!(FractionPolicy::from(false), FractionPolicy::default());
+    }
+}
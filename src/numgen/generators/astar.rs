@@ -1,9 +1,11 @@
 use clap::Args;
 use num_rational::Ratio;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     numgen::{Path, PathLimits, QueuedPath},
+    pickle,
     utils::NonZeroSign,
 };
 
@@ -11,18 +13,21 @@ use std::collections::BinaryHeap;
 
 use super::traits::{AStar, PathGenerator};
 
-#[pyclass(get_all, set_all)]
-#[derive(Clone, Copy, Args)]
+#[pyclass(module = "hexnumgen", get_all, set_all)]
+#[derive(Debug, Clone, Copy, Args, Serialize, Deserialize)]
 pub struct AStarOptions {}
 
-#[pymethods]
-impl AStarOptions {
-    #[new]
-    fn new() -> Self {
-        Self {}
+pickle::pymethods! {
+    impl AStarOptions {
+        #[new]
+        fn new() -> Self {
+            Self {}
+        }
     }
 }
 
+impl pickle::JsonPickle for AStarOptions {}
+
 pub struct AStarPathGenerator {
     // params
     limits: PathLimits,
@@ -35,12 +40,8 @@ pub struct AStarPathGenerator {
 impl PathGenerator for AStarPathGenerator {
     type Opts = AStarOptions;
 
-    fn new(target: Ratio<i64>, trim_larger: bool, allow_fractions: bool, _: AStarOptions) -> Self {
-        let mut gen = Self {
-            limits: PathLimits::unbounded(target, trim_larger, allow_fractions),
-            smallest: None,
-            frontier: BinaryHeap::new(),
-        };
+    fn new(target: Ratio<i64>, limits: PathLimits, _: AStarOptions) -> Self {
+        let mut gen = Self { limits, smallest: None, frontier: BinaryHeap::new() };
         gen.push_path(Path::zero(NonZeroSign::from(target)));
         gen
     }
@@ -51,8 +52,8 @@ impl PathGenerator for AStarPathGenerator {
 }
 
 impl AStar for AStarPathGenerator {
-    fn limits(&self) -> PathLimits {
-        self.limits
+    fn limits(&self) -> &PathLimits {
+        &self.limits
     }
 
     fn smallest(&self) -> &Option<Path> {
@@ -3,9 +3,11 @@ use itertools::Itertools;
 use num_rational::Ratio;
 use parking_lot::{Condvar, Mutex, RwLock};
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     numgen::{Path, PathLimits, QueuedPath, SharedPath},
+    pickle,
     traits::RwLockWriteIf,
     utils::NonZeroSign,
 };
@@ -18,20 +20,23 @@ use std::{
 
 use super::traits::{AStar, PathGenerator, Split};
 
-#[pyclass(get_all, set_all)]
-#[derive(Clone, Copy, Args)]
+#[pyclass(module = "hexnumgen", get_all, set_all)]
+#[derive(Debug, Clone, Copy, Args, Serialize, Deserialize)]
 pub struct AStarSplitOptions {
     pub num_threads: usize,
 }
 
-#[pymethods]
-impl AStarSplitOptions {
-    #[new]
-    fn new(num_threads: usize) -> Self {
-        Self { num_threads }
+pickle::pymethods! {
+    impl AStarSplitOptions {
+        #[new]
+        fn new(num_threads: usize) -> Self {
+            Self { num_threads }
+        }
     }
 }
 
+impl pickle::JsonPickle for AStarSplitOptions {}
+
 pub struct AStarParallelSplitPathGenerator {
     // params
     limits: PathLimits,
@@ -57,14 +62,11 @@ impl AStarParallelSplitPathGenerator {
 impl PathGenerator for AStarParallelSplitPathGenerator {
     type Opts = AStarSplitOptions;
 
-    fn new(
-        target: Ratio<i64>,
-        trim_larger: bool,
-        allow_fractions: bool,
-        AStarSplitOptions { num_threads }: AStarSplitOptions,
-    ) -> Self {
+    fn new(target: Ratio<i64>, limits: PathLimits, AStarSplitOptions { num_threads }: AStarSplitOptions) -> Self {
+        // validate rejects 0 threads, but generators can also be built directly or through the registry
+        let num_threads = num_threads.max(1);
         let mut gen = Self {
-            limits: PathLimits::unbounded(target, trim_larger, allow_fractions),
+            limits,
             num_threads,
             smallest: None,
             frontier: BinaryHeap::new(),
@@ -102,6 +104,7 @@ impl Split for AStarParallelSplitPathGenerator {
             shared_smallest: self.shared_smallest.clone(),
             free_threads: self.free_threads.clone(),
             done: self.done.clone(),
+            limits: self.limits.clone(),
             ..*self
         };
         thread::spawn(move || child_gen.do_search())
@@ -109,8 +112,8 @@ impl Split for AStarParallelSplitPathGenerator {
 }
 
 impl AStar for AStarParallelSplitPathGenerator {
-    fn limits(&self) -> PathLimits {
-        self.limits
+    fn limits(&self) -> &PathLimits {
+        &self.limits
     }
 
     fn smallest(&self) -> &Option<Path> {
@@ -130,10 +133,10 @@ impl AStar for AStarParallelSplitPathGenerator {
     }
 
     fn do_search(&mut self) {
-        while !self.frontier().is_empty() {
+        while !self.frontier().is_empty() && !self.limits.hooks.is_cancelled() {
             // check if another thread has found a better result, and update ours if so
             let shared_smallest = match &*self.shared_smallest.read() {
-                Some(s) if s.should_replace(&self.smallest) => Some(s.clone()),
+                Some(s) if s.should_replace(&self.smallest, self.limits.objective) => Some(s.clone()),
                 _ => None,
             };
             if let Some(new_smallest) = shared_smallest {
@@ -144,12 +147,15 @@ impl AStar for AStarParallelSplitPathGenerator {
             // if the above section emptied the frontier, update_frontier() just returns false, so this is skipped
             if self.update_frontier()
                 && let Some(new_smallest) = self.find_best_in_frontier()
-                && new_smallest.should_replace(self.smallest())
+                && new_smallest.should_replace(self.smallest(), self.limits.objective)
             {
                 let new_smallest = new_smallest.clone();
 
                 // if we found a better result than the shared one, update the shared one
-                if let Some(mut lock) = self.shared_smallest.write_if(|s| new_smallest.should_replace(s)) {
+                if let Some(mut lock) =
+                    self.shared_smallest.write_if(|s| new_smallest.should_replace(s, self.limits.objective))
+                {
+                    self.limits.hooks.improved(&new_smallest);
                     *lock = Some(new_smallest.clone());
                 }
 
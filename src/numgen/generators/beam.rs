@@ -2,26 +2,34 @@ use crate::numgen::{Bounds, Path, PathLimits, SharedPath};
 use clap::Args;
 use num_rational::Ratio;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::pickle;
 
 use super::traits::{BeamSearch, PathGenerator};
 
-#[pyclass(get_all, set_all)]
-#[derive(Clone, Copy, Args)]
+#[pyclass(module = "hexnumgen", get_all, set_all)]
+#[derive(Debug, Clone, Copy, Args, Serialize, Deserialize)]
 pub struct BeamOptions {
     #[command(flatten)]
+    #[serde(default)]
     pub bounds: Bounds,
     #[arg(short, long, default_value_t = 25)]
+    #[serde(default = "super::default_carryover")]
     pub carryover: usize,
 }
 
-#[pymethods]
-impl BeamOptions {
-    #[new]
-    fn new(bounds: Bounds, carryover: usize) -> Self {
-        Self { bounds, carryover }
+pickle::pymethods! {
+    impl BeamOptions {
+        #[new]
+        fn new(bounds: Bounds, carryover: usize) -> Self {
+            Self { bounds, carryover }
+        }
     }
 }
 
+impl pickle::JsonPickle for BeamOptions {}
+
 pub struct BeamPathGenerator {
     // params
     limits: PathLimits,
@@ -37,14 +45,9 @@ pub struct BeamPathGenerator {
 impl PathGenerator for BeamPathGenerator {
     type Opts = BeamOptions;
 
-    fn new(
-        target: Ratio<i64>,
-        trim_larger: bool,
-        allow_fractions: bool,
-        Self::Opts { bounds, carryover }: Self::Opts,
-    ) -> Self {
+    fn new(target: Ratio<i64>, limits: PathLimits, Self::Opts { bounds, carryover }: Self::Opts) -> Self {
         Self {
-            limits: PathLimits::bounded(target, trim_larger, allow_fractions, bounds),
+            limits: limits.with_bounds(bounds),
             carryover,
             smallest: SharedPath::default(),
             paths: vec![Path::zero(target.into())],
@@ -57,8 +60,8 @@ impl PathGenerator for BeamPathGenerator {
 }
 
 impl BeamSearch for BeamPathGenerator {
-    fn limits(&self) -> PathLimits {
-        self.limits
+    fn limits(&self) -> &PathLimits {
+        &self.limits
     }
 
     fn carryover(&self) -> usize {
@@ -1,11 +1,13 @@
 use clap::Args;
 use num_rational::Ratio;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 
 use crate::{
     hex_math::Angle,
     numgen::{Path, PathLimits, SharedPath},
+    pickle,
     threadpool::ThreadPool,
     Bounds,
 };
@@ -15,24 +17,29 @@ use super::{
     BeamOptions,
 };
 
-#[pyclass(get_all, set_all)]
-#[derive(Clone, Copy, Args)]
+#[pyclass(module = "hexnumgen", get_all, set_all)]
+#[derive(Debug, Clone, Copy, Args, Serialize, Deserialize)]
 pub struct BeamPoolOptions {
     #[command(flatten)]
+    #[serde(default)]
     pub bounds: Bounds,
     #[arg(short, long, default_value_t = 25)]
+    #[serde(default = "super::default_carryover")]
     pub carryover: usize,
     pub num_threads: usize,
 }
 
-#[pymethods]
-impl BeamPoolOptions {
-    #[new]
-    fn new(bounds: Bounds, carryover: usize, num_threads: usize) -> Self {
-        Self { bounds, carryover, num_threads }
+pickle::pymethods! {
+    impl BeamPoolOptions {
+        #[new]
+        fn new(bounds: Bounds, carryover: usize, num_threads: usize) -> Self {
+            Self { bounds, carryover, num_threads }
+        }
     }
 }
 
+impl pickle::JsonPickle for BeamPoolOptions {}
+
 impl From<BeamPoolOptions> for BeamOptions {
     fn from(BeamPoolOptions { bounds, carryover, .. }: BeamPoolOptions) -> Self {
         Self { bounds, carryover }
@@ -53,22 +60,22 @@ pub struct BeamParallelPoolPathGenerator {
 impl PathGenerator for BeamParallelPoolPathGenerator {
     type Opts = BeamPoolOptions;
 
-    fn new(
-        target: Ratio<i64>,
-        trim_larger: bool,
-        allow_fractions: bool,
-        Self::Opts { bounds, carryover, num_threads }: Self::Opts,
-    ) -> Self {
-        let limits = PathLimits::bounded(target, trim_larger, allow_fractions, bounds);
+    fn new(target: Ratio<i64>, limits: PathLimits, Self::Opts { bounds, carryover, num_threads }: Self::Opts) -> Self {
+        // validate rejects 0 threads, but generators can also be built directly or through the registry
+        let num_threads = num_threads.max(1);
+        let limits = limits.with_bounds(bounds);
         let smallest = SharedPath::default();
 
         let pool = {
-            // make a copy of the rwlock for the threads in the pool to use
+            // make a copy of the rwlock and limits for the threads in the pool to use
             let smallest = smallest.clone();
+            let limits = limits.clone();
 
             ThreadPool::new(num_threads, move |p: Path| {
                 Angle::iter()
-                    .filter_map(|a| p.try_with_angle(a, limits, |n| n.should_replace(&smallest.read())).ok())
+                    .filter_map(|a| {
+                        p.try_with_angle(a, &limits, |n| n.should_replace(&smallest.read(), limits.objective)).ok()
+                    })
                     .collect()
             })
         };
@@ -82,8 +89,8 @@ impl PathGenerator for BeamParallelPoolPathGenerator {
 }
 
 impl BeamSearch for BeamParallelPoolPathGenerator {
-    fn limits(&self) -> PathLimits {
-        self.limits
+    fn limits(&self) -> &PathLimits {
+        &self.limits
     }
 
     fn carryover(&self) -> usize {
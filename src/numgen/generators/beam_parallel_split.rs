@@ -5,33 +5,40 @@ use std::{
 
 use crate::{
     numgen::{Bounds, Path, PathLimits, SharedPath},
+    pickle,
     utils::drain_every_other,
 };
 use clap::Args;
 use num_rational::Ratio;
 use parking_lot::{Condvar, Mutex, RwLock};
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use super::traits::{BeamSearch, PathGenerator, Split};
 
-#[pyclass(get_all, set_all)]
-#[derive(Clone, Copy, Args)]
+#[pyclass(module = "hexnumgen", get_all, set_all)]
+#[derive(Debug, Clone, Copy, Args, Serialize, Deserialize)]
 pub struct BeamSplitOptions {
     #[command(flatten)]
+    #[serde(default)]
     pub bounds: Bounds,
     #[arg(short, long, default_value_t = 25)]
+    #[serde(default = "super::default_carryover")]
     pub carryover: usize,
     pub num_threads: usize,
 }
 
-#[pymethods]
-impl BeamSplitOptions {
-    #[new]
-    fn new(bounds: Bounds, carryover: usize, num_threads: usize) -> Self {
-        Self { bounds, carryover, num_threads }
+pickle::pymethods! {
+    impl BeamSplitOptions {
+        #[new]
+        fn new(bounds: Bounds, carryover: usize, num_threads: usize) -> Self {
+            Self { bounds, carryover, num_threads }
+        }
     }
 }
 
+impl pickle::JsonPickle for BeamSplitOptions {}
+
 pub struct BeamParallelSplitPathGenerator {
     // params
     limits: PathLimits,
@@ -48,14 +55,11 @@ pub struct BeamParallelSplitPathGenerator {
 impl PathGenerator for BeamParallelSplitPathGenerator {
     type Opts = BeamSplitOptions;
 
-    fn new(
-        target: Ratio<i64>,
-        trim_larger: bool,
-        allow_fractions: bool,
-        Self::Opts { bounds, carryover, num_threads }: Self::Opts,
-    ) -> Self {
+    fn new(target: Ratio<i64>, limits: PathLimits, Self::Opts { bounds, carryover, num_threads }: Self::Opts) -> Self {
+        // validate rejects 0 threads, but generators can also be built directly or through the registry
+        let num_threads = num_threads.max(1);
         Self {
-            limits: PathLimits::bounded(target, trim_larger, allow_fractions, bounds),
+            limits: limits.with_bounds(bounds),
             carryover,
             smallest: SharedPath::default(),
             paths: vec![Path::zero(target.into())],
@@ -92,6 +96,7 @@ impl Split for BeamParallelSplitPathGenerator {
             smallest: self.smallest.clone(),
             free_threads: self.free_threads.clone(),
             done: self.done.clone(),
+            limits: self.limits.clone(),
             ..*self
         };
         thread::spawn(move || child_gen.do_search())
@@ -99,8 +104,8 @@ impl Split for BeamParallelSplitPathGenerator {
 }
 
 impl BeamSearch for BeamParallelSplitPathGenerator {
-    fn limits(&self) -> PathLimits {
-        self.limits
+    fn limits(&self) -> &PathLimits {
+        &self.limits
     }
 
     fn carryover(&self) -> usize {
@@ -121,7 +126,7 @@ impl BeamSearch for BeamParallelSplitPathGenerator {
 
     fn do_search(&mut self) {
         // main loop
-        while !self.paths.is_empty() {
+        while !self.paths.is_empty() && !self.limits.hooks.is_cancelled() {
             self.expand();
             self.trim_to_best();
             self.update_smallest();
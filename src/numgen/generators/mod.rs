@@ -3,6 +3,7 @@ mod astar_parallel_split;
 mod beam;
 mod beam_parallel_pool;
 mod beam_parallel_split;
+mod registry;
 pub mod traits;
 
 pub use astar::{AStarOptions, AStarPathGenerator};
@@ -10,3 +11,8 @@ pub use astar_parallel_split::{AStarParallelSplitPathGenerator, AStarSplitOption
 pub use beam::{BeamOptions, BeamPathGenerator};
 pub use beam_parallel_pool::{BeamParallelPoolPathGenerator, BeamPoolOptions};
 pub use beam_parallel_split::{BeamParallelSplitPathGenerator, BeamSplitOptions};
+pub use registry::{GeneratorFactory, GeneratorRegistry, NamedOptions};
+
+fn default_carryover() -> usize {
+    25
+}
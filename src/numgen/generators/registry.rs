@@ -0,0 +1,230 @@
+use std::{collections::BTreeMap, sync::Arc, sync::OnceLock};
+
+use clap::Args;
+use num_rational::Ratio;
+use parking_lot::RwLock;
+use pyo3::{prelude::*, types::PyDict};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    errors::{HexError, HexResult},
+    numgen::{Path, PathLimits},
+    pickle, GeneratorOptions,
+};
+
+use super::traits::PathGenerator;
+
+/// Builds and runs a generator from its JSON parameters.
+pub type GeneratorFactory = Arc<dyn Fn(Ratio<i64>, PathLimits, Value) -> HexResult<Option<Path>> + Send + Sync>;
+
+/// Maps algorithm names to generators, so they can be picked with a string from configs, the CLI or Python.
+///
+/// The built-in algorithms are always registered under the same names used by [`GeneratorOptions`]. Other crates can
+/// add their own with [`GeneratorRegistry::register`] on the [global](GeneratorRegistry::global) registry.
+pub struct GeneratorRegistry {
+    factories: BTreeMap<String, GeneratorFactory>,
+}
+
+impl GeneratorRegistry {
+    /// Returns a registry containing only the built-in algorithms.
+    pub fn new() -> Self {
+        let mut registry = Self { factories: BTreeMap::new() };
+        registry.register_builtin("beam", GeneratorOptions::Beam);
+        registry.register_builtin("beam-pool", GeneratorOptions::BeamPool);
+        registry.register_builtin("beam-split", GeneratorOptions::BeamSplit);
+        registry.register_builtin("astar", GeneratorOptions::AStar);
+        registry.register_builtin("astar-split", GeneratorOptions::AStarSplit);
+        registry
+    }
+
+    // goes through GeneratorOptions so the built-ins get validated the same way everywhere
+    fn register_builtin<O>(&mut self, name: &str, variant: fn(O) -> GeneratorOptions)
+    where
+        O: DeserializeOwned + 'static,
+    {
+        self.register_fn(name, move |target, limits, params| {
+            let options = variant(deserialize_params(Value::Object(into_object(params)?))?);
+            options.validate()?;
+            options.run(target, limits)
+        });
+    }
+
+    /// The registry used by [`GeneratorOptions::Named`].
+    pub fn global() -> &'static RwLock<Self> {
+        static GLOBAL: OnceLock<RwLock<GeneratorRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(|| RwLock::new(Self::new()))
+    }
+
+    /// Registers `G` under `name`, deserializing its options from the parameters. Replaces any existing generator
+    /// with the same name.
+    pub fn register<G>(&mut self, name: impl Into<String>)
+    where
+        G: PathGenerator,
+        G::Opts: DeserializeOwned,
+    {
+        self.register_fn(name, |target, limits, params| {
+            let opts = deserialize_params(Value::Object(into_object(params)?))?;
+            Ok(G::new(target, limits, opts).run())
+        });
+    }
+
+    /// Registers an arbitrary function under `name`. Replaces any existing generator with the same name.
+    pub fn register_fn<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn(Ratio<i64>, PathLimits, Value) -> HexResult<Option<Path>> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Arc::new(factory));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Returns the names of all registered generators, in alphabetical order.
+    pub fn names(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+
+    pub fn get(&self, name: &str) -> HexResult<GeneratorFactory> {
+        self.factories.get(name).cloned().ok_or_else(|| HexError::UnknownGenerator(name.to_string()))
+    }
+}
+
+impl Default for GeneratorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Treats missing parameters as an empty object, since most generators have defaults for everything.
+fn into_object(params: Value) -> HexResult<Map<String, Value>> {
+    match params {
+        Value::Null => Ok(Map::new()),
+        Value::Object(map) => Ok(map),
+        other => Err(HexError::InvalidOptions(format!("expected an object of parameters, got `{other}`"))),
+    }
+}
+
+fn deserialize_params<T: DeserializeOwned>(params: Value) -> HexResult<T> {
+    serde_json::from_value(params).map_err(|err| HexError::InvalidOptions(err.to_string()))
+}
+
+/// Options for any generator in the [global registry](GeneratorRegistry::global), looked up by name.
+#[pyclass(module = "hexnumgen")]
+#[derive(Debug, Clone, Args, Serialize, Deserialize)]
+pub struct NamedOptions {
+    /// Name of the registered generator
+    #[pyo3(get)]
+    pub name: String,
+    /// Generator parameters as a JSON object
+    #[arg(short, long, value_parser = parse_params, default_value = "{}")]
+    #[serde(flatten)]
+    pub params: Map<String, Value>,
+}
+
+pickle::pymethods! {
+    impl NamedOptions {
+        #[new]
+        #[pyo3(signature = (name, params=None))]
+        fn py_new(py: Python, name: String, params: Option<&PyDict>) -> PyResult<Self> {
+            let params = match params {
+                Some(params) => {
+                    let json: String = py.import("json")?.call_method1("dumps", (params,))?.extract()?;
+                    parse_params(&json)?
+                }
+                None => Map::new(),
+            };
+            Ok(Self { name, params })
+        }
+
+        #[getter]
+        fn params<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+            let json = Value::Object(self.params.clone()).to_string();
+            Ok(py.import("json")?.call_method1("loads", (json,))?.downcast()?)
+        }
+    }
+}
+
+impl pickle::JsonPickle for NamedOptions {}
+
+fn parse_params(json: &str) -> HexResult<Map<String, Value>> {
+    into_object(serde_json::from_str(json).map_err(|err| HexError::InvalidOptions(err.to_string()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::numgen::{
+        generators::{
+            AStarParallelSplitPathGenerator, AStarPathGenerator, BeamParallelPoolPathGenerator,
+            BeamParallelSplitPathGenerator,
+        },
+        Ceiling, FractionPolicy, Objective,
+    };
+
+    fn limits(target: i64) -> PathLimits {
+        PathLimits::new(target.into(), Ceiling::default(), FractionPolicy::default(), Objective::default())
+    }
+
+    #[test]
+    fn builtins_are_registered() {
+        let registry = GeneratorRegistry::new();
+        assert_eq!(registry.names(), ["astar", "astar-split", "beam", "beam-pool", "beam-split"]);
+        assert!(matches!(registry.get("nope"), Err(HexError::UnknownGenerator(name)) if name == "nope"));
+    }
+
+    #[test]
+    fn builtins_validate_their_params() {
+        let registry = GeneratorRegistry::new();
+        let beam = registry.get("beam").unwrap();
+        assert!(beam(10.into(), limits(10), json!({"carryover": 5})).unwrap().is_some());
+        assert!(beam(10.into(), limits(10), Value::Null).unwrap().is_some());
+        assert!(matches!(beam(10.into(), limits(10), json!({"carryover": "5"})), Err(HexError::InvalidOptions(_))));
+        assert!(matches!(beam(10.into(), limits(10), json!([1])), Err(HexError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn registers_custom_generators() {
+        let mut registry = GeneratorRegistry::new();
+        registry.register::<AStarPathGenerator>("my-astar");
+        registry.register_fn("nothing", |_, _, _| Ok(None));
+        assert!(registry.contains("my-astar") && registry.contains("nothing"));
+
+        let path = registry.get("my-astar").unwrap()(12.into(), limits(12), Value::Null).unwrap().unwrap();
+        assert_eq!(path.value(), 12.into());
+        assert!(registry.get("nothing").unwrap()(12.into(), limits(12), Value::Null).unwrap().is_none());
+    }
+
+    #[test]
+    fn unvalidated_generators_use_at_least_one_thread() {
+        let mut registry = GeneratorRegistry::new();
+        registry.register::<AStarParallelSplitPathGenerator>("astar-split-unchecked");
+        registry.register::<BeamParallelSplitPathGenerator>("beam-split-unchecked");
+        registry.register::<BeamParallelPoolPathGenerator>("beam-pool-unchecked");
+        for name in ["astar-split-unchecked", "beam-split-unchecked", "beam-pool-unchecked"] {
+            let generator = registry.get(name).unwrap();
+            let path = generator(12.into(), limits(12), json!({"num_threads": 0})).unwrap().unwrap();
+            assert_eq!(path.value(), 12.into(), "{name}");
+        }
+        // the built-ins still reject it
+        assert!(registry.get("astar-split").unwrap()(12.into(), limits(12), json!({"num_threads": 0})).is_err());
+    }
+
+    #[test]
+    fn named_options_use_the_global_registry() {
+        GeneratorRegistry::global().write().register_fn("registry-test", |target, _, params| {
+            assert_eq!(params, json!({"answer": 42}));
+            Ok(Some(Path::zero(target.into())))
+        });
+
+        let options: GeneratorOptions = serde_json::from_value(json!({"name": "registry-test", "answer": 42})).unwrap();
+        assert!(matches!(&options, GeneratorOptions::Named(named) if named.name == "registry-test"));
+        assert!(options.run(3.into(), limits(3)).unwrap().is_some());
+
+        let unknown: GeneratorOptions = serde_json::from_value(json!({"name": "registry-test-missing"})).unwrap();
+        assert!(matches!(unknown.run(3.into(), limits(3)), Err(HexError::UnknownGenerator(_))));
+    }
+}
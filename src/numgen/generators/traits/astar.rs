@@ -10,7 +10,7 @@ use crate::{
 };
 
 pub trait AStar {
-    fn limits(&self) -> PathLimits;
+    fn limits(&self) -> &PathLimits;
     fn smallest(&self) -> &Option<Path>;
     fn smallest_mut(&mut self) -> &mut Option<Path>;
     fn frontier(&self) -> &BinaryHeap<QueuedPath>;
@@ -33,11 +33,12 @@ pub trait AStar {
     }
 
     fn do_search(&mut self) {
-        while !self.frontier().is_empty() {
+        while !self.frontier().is_empty() && !self.limits().hooks.is_cancelled() {
             if self.update_frontier()
                 && let Some(new_smallest) = self.find_best_in_frontier()
-                && new_smallest.should_replace(self.smallest())
+                && new_smallest.should_replace(self.smallest(), self.limits().objective)
             {
+                self.limits().hooks.improved(new_smallest);
                 self.update_smallest_and_prune(new_smallest.clone());
             }
         }
@@ -55,7 +56,7 @@ pub trait AStar {
             .iter()
             .map(|qp| &qp.path)
             .filter(|path| path.value() == self.target())
-            .min_by_key(|path| (path.bounds().quasi_area(), path.len()))
+            .min_by_key(|path| self.limits().objective.path_key(path))
     }
 
     fn push_path(&mut self, path: Path) {
@@ -69,7 +70,10 @@ pub trait AStar {
 
     fn next_paths(&self, path: Path) -> Vec<Path> {
         Angle::iter()
-            .filter_map(|a| path.try_with_angle(a, self.limits(), |n| n.should_replace(self.smallest())).ok())
+            .filter_map(|a| {
+                path.try_with_angle(a, self.limits(), |n| n.should_replace(self.smallest(), self.limits().objective))
+                    .ok()
+            })
             .collect()
     }
 
@@ -92,39 +96,43 @@ pub trait AStar {
 
     fn update_smallest_and_prune(&mut self, new_smallest: Path) {
         let new_smallest = Some(new_smallest);
-        self.frontier_mut().retain(|qp| qp.path.should_replace(&new_smallest));
+        let objective = self.limits().objective;
+        self.frontier_mut().retain(|qp| qp.path.should_replace(&new_smallest, objective));
         *self.smallest_mut() = new_smallest;
     }
 
     fn heuristic(&mut self, path: &Path) -> usize {
-        let mut val = path.value();
-        let mut target = self.target();
-        let mut heuristic = path.len();
-
-        if val.is_zero() {
-            heuristic += 1;
-
-            if target > 10.into() {
-                val += 10;
-            } else if target > 5.into() {
-                val += 5;
-            } else {
-                val += 1;
-            }
-        }
+        let limits = self.limits();
+        let estimate = path.len() + limits.estimate_steps(path.value());
 
-        if !target.is_zero() {
-            while val > target {
-                val /= 2;
-                heuristic += 1;
-            }
+        // among paths with the same estimate, try the ones that can't double under the ceiling first
+        // they only need additions, so they tend to reach the target soonest, and an early result prunes the frontier
+        2 * estimate + usize::from(!limits.must_add(path.value()))
+    }
+}
 
-            while target / 2 > val {
-                target /= 2;
-                heuristic += 1;
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hex_math::Direction,
+        numgen::{
+            generators::{traits::PathGenerator, AStarOptions, AStarPathGenerator},
+            Ceiling, FractionPolicy, Objective,
+        },
+    };
+
+    fn heuristic(ceiling: Ceiling, pattern: &str) -> usize {
+        let limits = PathLimits::new(100.into(), ceiling, FractionPolicy::default(), Objective::default());
+        let mut generator = AStarPathGenerator::new(100.into(), limits, AStarOptions {});
+        generator.heuristic(&Path::from_pattern(Direction::SouthEast, pattern).unwrap())
+    }
 
-        heuristic
+    #[test]
+    fn ceiling_breaks_ties_towards_paths_that_must_add() {
+        // 60 and 50 are the same length and both within a doubling of 100, but only 50 can double under a ceiling of 100
+        let (sixty, fifty) = ("aqaaqeaa", "aqaaeaqa");
+        assert_eq!(heuristic(Ceiling::Unbounded, sixty), heuristic(Ceiling::Unbounded, fifty));
+        assert!(heuristic(Ceiling::default(), sixty) < heuristic(Ceiling::default(), fifty));
     }
 }
@@ -10,7 +10,7 @@ use crate::{
 };
 
 pub trait BeamSearch {
-    fn limits(&self) -> PathLimits;
+    fn limits(&self) -> &PathLimits;
     fn carryover(&self) -> usize;
     fn smallest(&self) -> &SharedPath;
     fn paths(&self) -> &Vec<Path>;
@@ -33,7 +33,7 @@ pub trait BeamSearch {
     }
 
     fn do_search(&mut self) {
-        while !self.paths().is_empty() {
+        while !self.paths().is_empty() && !self.limits().hooks.is_cancelled() {
             self.expand();
             self.trim_to_best();
             self.update_smallest();
@@ -52,7 +52,12 @@ pub trait BeamSearch {
             .paths()
             .iter()
             .cartesian_product(Angle::iter())
-            .filter_map(|(p, a)| p.try_with_angle(a, self.limits(), |n| n.should_replace(&self.smallest().read())).ok())
+            .filter_map(|(p, a)| {
+                p.try_with_angle(a, self.limits(), |n| {
+                    n.should_replace(&self.smallest().read(), self.limits().objective)
+                })
+                .ok()
+            })
             .collect();
     }
 
@@ -77,15 +82,22 @@ pub trait BeamSearch {
     fn trim_to_best(&mut self) {
         let mut rest: Vec<_> = self.paths_mut().drain(..).collect();
         let target = self.target();
+        let limits = self.limits().clone();
+        let closeness = |path: &Path| {
+            let value = path.value();
+            (limits.estimate_steps(value) + limits.ceiling_penalty(value), value.abs_diff(target))
+        };
 
         self.filter_by_key(&mut rest, |path| path.len()); // shortest
-        self.filter_by_key(&mut rest, |path| path.value().abs_diff(target)); // closest to target
+        self.filter_by_key(&mut rest, closeness); // fewest steps left to the target, then closest to it
         self.filter_by_key(&mut rest, |path| path.num_points()); // fewest points
     }
 
     fn update_smallest(&mut self) {
         // appease the borrow checker
         let target = self.target();
+        let objective = self.limits().objective;
+        let hooks = self.limits().hooks.clone();
         let smallest = self.smallest().clone();
 
         // prune completed paths from the beam
@@ -96,7 +108,8 @@ pub trait BeamSearch {
             }
 
             // if it's a valid result, only acquire the write lock if it's better than the current smallest value
-            if let Some(mut smallest_lock) = smallest.write_if(|s| path.should_replace(s)) {
+            if let Some(mut smallest_lock) = smallest.write_if(|s| path.should_replace(s, objective)) {
+                hooks.improved(path);
                 *smallest_lock = Some(path.clone());
             }
             false // don't keep expanding paths that already reached the target
@@ -1,11 +1,11 @@
 use num_rational::Ratio;
 
-use crate::numgen::Path;
+use crate::numgen::{Path, PathLimits};
 
 pub trait PathGenerator {
     type Opts;
 
-    fn new(target: Ratio<i64>, trim_larger: bool, allow_fractions: bool, opts: Self::Opts) -> Self
+    fn new(target: Ratio<i64>, limits: PathLimits, opts: Self::Opts) -> Self
     where
         Self: Sized;
 
@@ -0,0 +1,92 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use super::Path;
+
+type ImprovementCallback = Arc<dyn Fn(&Path) + Send + Sync>;
+
+/// Callbacks for watching a search while it runs. These are shared between every thread of a parallel search, so they
+/// may be called from any thread.
+#[derive(Clone, Default)]
+pub struct SearchHooks {
+    on_improvement: Option<ImprovementCallback>,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl SearchHooks {
+    /// Calls `f` whenever the search finds a better path to the target than any it's found so far. Keep it quick, since
+    /// parallel searches hold a lock on the shared result while it runs.
+    pub fn on_improvement(mut self, f: impl Fn(&Path) + Send + Sync + 'static) -> Self {
+        self.on_improvement = Some(Arc::new(f));
+        self
+    }
+
+    /// Stops the search early once `flag` is set, eg. from another thread when the user presses Ctrl-C. The search
+    /// returns the best path it found before stopping, if any.
+    pub fn cancel_on(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(flag);
+        self
+    }
+
+    pub(crate) fn improved(&self, path: &Path) {
+        if let Some(f) = &self.on_improvement {
+            f(path);
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+}
+
+impl fmt::Debug for SearchHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SearchHooks")
+            .field("on_improvement", &self.on_improvement.is_some())
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::utils::NonZeroSign;
+
+    #[test]
+    fn follows_the_cancel_flag() {
+        assert!(!SearchHooks::default().is_cancelled());
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let hooks = SearchHooks::default().cancel_on(flag.clone());
+        let shared = hooks.clone();
+        assert!(!hooks.is_cancelled());
+        flag.store(true, Ordering::Relaxed);
+        assert!(hooks.is_cancelled());
+        assert!(shared.is_cancelled());
+        assert_eq!(format!("{hooks:?}"), "SearchHooks { on_improvement: false, cancelled: true }");
+    }
+
+    #[test]
+    fn calls_the_improvement_callback() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hooks = SearchHooks::default().on_improvement({
+            let calls = calls.clone();
+            move |path| {
+                assert_eq!(path.pattern(), "aqaa");
+                calls.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        hooks.improved(&Path::zero(NonZeroSign::Positive));
+        hooks.clone().improved(&Path::zero(NonZeroSign::Positive));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        SearchHooks::default().improved(&Path::zero(NonZeroSign::Positive));
+    }
+}
@@ -1,11 +1,19 @@
 mod bounds;
+mod ceiling;
+mod fractions;
+mod hooks;
 mod minmax;
+mod objective;
 mod path;
 mod queued_path;
 
 pub mod generators;
 
 pub use bounds::Bounds;
+pub use ceiling::Ceiling;
+pub use fractions::FractionPolicy;
+pub use hooks::SearchHooks;
 pub use minmax::MinMax;
+pub use objective::Objective;
 pub use path::{Path, PathLimits, SharedPath};
 pub use queued_path::QueuedPath;
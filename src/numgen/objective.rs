@@ -0,0 +1,46 @@
+use clap::ValueEnum;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::pickle;
+
+use super::{Bounds, Path};
+
+/// What makes one pattern better than another.
+#[pyclass(module = "hexnumgen")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// Smallest quasi-area (q*r*s), then fewest segments
+    #[default]
+    QuasiArea,
+    /// Fewest segments, then smallest quasi-area
+    Segments,
+    /// Smallest largest dimension, then smallest quasi-area
+    LargestDimension,
+    /// Fewest points, then fewest segments
+    Points,
+}
+
+pickle::pymethods! {
+    impl Objective {}
+}
+
+impl pickle::JsonPickle for Objective {}
+
+impl Objective {
+    /// Returns the metrics compared by this objective, most important first. Lower is better.
+    pub fn key(&self, bounds: Bounds, num_segments: usize, num_points: usize) -> (usize, usize) {
+        let quasi_area = bounds.quasi_area() as usize;
+        match self {
+            Self::QuasiArea => (quasi_area, num_segments),
+            Self::Segments => (num_segments, quasi_area),
+            Self::LargestDimension => (bounds.largest_dimension() as usize, quasi_area),
+            Self::Points => (num_points, num_segments),
+        }
+    }
+
+    pub fn path_key(&self, path: &Path) -> (usize, usize) {
+        self.key(path.bounds(), path.len(), path.num_points())
+    }
+}
@@ -1,6 +1,8 @@
 use itertools::Itertools;
 use num_rational::Ratio;
+use num_traits::{CheckedDiv, CheckedMul, Zero};
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use std::{collections::HashSet, sync::Arc};
 
 use crate::{
@@ -10,29 +12,110 @@ use crate::{
     utils::{cloned_push, cloned_union_single, NonZeroSign},
 };
 
-use super::{Bounds, MinMax};
+use super::{Bounds, Ceiling, FractionPolicy, MinMax, Objective, SearchHooks};
 
 pub type SharedPath = Arc<RwLock<Option<Path>>>;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct PathLimits {
     pub target: Ratio<u64>,
-    pub trim_larger: bool,
-    pub allow_fractions: bool,
+    pub max_value: Option<Ratio<u64>>,
+    pub fractions: FractionPolicy,
+    pub objective: Objective,
     pub bounds: Option<Bounds>,
+    pub hooks: SearchHooks,
 }
 
 impl PathLimits {
-    pub fn bounded(signed_target: Ratio<i64>, trim_larger: bool, allow_fractions: bool, bounds: Bounds) -> Self {
-        Self { target: signed_target.unsigned_abs(), trim_larger, allow_fractions, bounds: Some(bounds) }
+    pub fn new(signed_target: Ratio<i64>, ceiling: Ceiling, fractions: FractionPolicy, objective: Objective) -> Self {
+        let target = signed_target.unsigned_abs();
+        Self {
+            target,
+            max_value: ceiling.max_value(target),
+            fractions,
+            objective,
+            bounds: None,
+            hooks: SearchHooks::default(),
+        }
+    }
+
+    pub fn with_bounds(self, bounds: Bounds) -> Self {
+        Self { bounds: Some(bounds), ..self }
+    }
+
+    pub fn with_hooks(self, hooks: SearchHooks) -> Self {
+        Self { hooks, ..self }
+    }
+
+    /// Returns true if doubling `value` would stay under the ceiling.
+    pub fn can_double(&self, value: Ratio<u64>) -> bool {
+        let doubled = value.checked_mul(&2.into());
+        self.max_value.map_or(true, |max_value| doubled.is_some_and(|doubled| doubled <= max_value))
     }
 
-    pub fn unbounded(signed_target: Ratio<i64>, trim_larger: bool, allow_fractions: bool) -> Self {
-        Self { target: signed_target.unsigned_abs(), trim_larger, allow_fractions, bounds: None }
+    /// Returns true if `value` is below the target but doubling it would pass the ceiling, so the rest has to be added.
+    pub fn must_add(&self, value: Ratio<u64>) -> bool {
+        value < self.target && !self.can_double(value)
+    }
+
+    /// Optimistic estimate of how many more angles a path with this value needs to reach the target, only counting the
+    /// doublings or halvings.
+    pub fn estimate_steps(&self, value: Ratio<u64>) -> usize {
+        let mut val = value;
+        let mut target = self.target;
+        let mut steps = 0;
+
+        if val.is_zero() {
+            steps += 1;
+
+            if target > 10.into() {
+                val += 10;
+            } else if target > 5.into() {
+                val += 5;
+            } else {
+                val += 1;
+            }
+        }
+
+        // halving very precise values (eg. exact doubles) can overflow the denominator, which just stops counting early
+        let half = |value: Ratio<u64>| value.checked_div(&2.into());
+        if !target.is_zero() {
+            while val > target {
+                let Some(halved) = half(val) else { break };
+                val = halved;
+                steps += 1;
+            }
+
+            while let Some(halved) = half(target).filter(|&halved| halved > val) {
+                target = halved;
+                steps += 1;
+            }
+        }
+
+        steps
+    }
+
+    /// Extra angles that [`estimate_steps`](Self::estimate_steps) misses when `value` [must add](Self::must_add) the
+    /// rest, either directly or by halving, adding and doubling back again.
+    pub fn ceiling_penalty(&self, value: Ratio<u64>) -> usize {
+        if !self.must_add(value) {
+            return 0;
+        }
+
+        let mut remainder = self.target - value;
+        let mut steps = 1;
+        while remainder > 10.into() {
+            remainder /= 2;
+            steps += 2;
+        }
+        steps
     }
 
     fn test_value(&self, new_value: Ratio<u64>) -> HexResult<Ratio<u64>> {
-        if self.trim_larger && new_value > self.target || !self.allow_fractions && !new_value.is_integer() {
+        // the target itself was already checked against the fraction policy before starting
+        if self.max_value.is_some_and(|max_value| new_value > max_value)
+            || new_value != self.target && !self.fractions.allows_intermediate(new_value)
+        {
             return Err(HexError::OutOfLimits);
         }
         Ok(new_value)
@@ -47,7 +130,15 @@ impl PathLimits {
     }
 }
 
-#[derive(Clone)]
+/// The serialized form of a [`Path`]. Everything else about the path can be recomputed from these.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathSnapshot {
+    pub direction: Direction,
+    pub pattern: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "PathSnapshot", try_from = "PathSnapshot")]
 pub struct Path {
     value: Ratio<u64>,
     segments: Vec<Segment>,
@@ -64,12 +155,38 @@ impl Path {
         }
         .unwrap();
 
+        Self::from_segments(0.into(), segments)
+    }
+
+    /// Decodes a number pattern, eg. from [`Path::pattern`]. The sign isn't stored in the path, so negative patterns
+    /// give the absolute value of the number.
+    pub fn from_pattern(direction: Direction, pattern: &str) -> HexResult<Self> {
+        let (prefix, angles) = match pattern.get(..4) {
+            Some(prefix @ ("aqaa" | "dedd")) => (prefix, &pattern[4..]),
+            _ => return Err(HexError::NotANumber(pattern.to_string())),
+        };
+
+        let mut path = Self::from_segments(0.into(), get_pattern_segments(direction, prefix)?);
+        for c in angles.chars() {
+            let angle = Angle::try_from(c)?;
+            let new_segment = path.next_segment(angle)?;
+
+            path.value = angle.apply_to(path.value)?;
+            path.minmax = path.minmax.with_point(new_segment.end());
+            path.points_set.insert(new_segment.end());
+            path.segments_set.insert(new_segment);
+            path.segments.push(new_segment);
+        }
+        Ok(path)
+    }
+
+    fn from_segments(value: Ratio<u64>, segments: Vec<Segment>) -> Self {
         Self {
-            value: 0.into(),
-            segments: segments.clone(),
+            value,
             segments_set: HashSet::from_iter(segments.clone()),
             points_set: HashSet::from_iter(segments.iter().flat_map(|segment| [segment.root(), segment.end()])),
             minmax: MinMax::from(&segments),
+            segments,
         }
     }
 
@@ -85,6 +202,10 @@ impl Path {
         self.segments.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
     pub fn num_points(&self) -> usize {
         self.points_set.len()
     }
@@ -97,14 +218,15 @@ impl Path {
         Ok(new_segment)
     }
 
-    pub fn should_replace(&self, other: &Option<Path>) -> bool {
+    pub fn should_replace(&self, other: &Option<Path>, objective: Objective) -> bool {
+        // only compare the most important metric, since that's the one that can't improve as a path gets longer
         match other {
-            Some(other) => self.bounds().is_better_than(other.bounds()),
+            Some(other) => objective.path_key(self).0 < objective.path_key(other).0,
             None => true,
         }
     }
 
-    pub fn try_with_angle<F>(&self, angle: Angle, limits: PathLimits, f: F) -> HexResult<Self>
+    pub fn try_with_angle<F>(&self, angle: Angle, limits: &PathLimits, f: F) -> HexResult<Self>
     where
         F: FnOnce(&Path) -> bool,
     {
@@ -143,3 +265,91 @@ impl Path {
         self.segments.iter().tuple_windows().map(|(a, b)| char::from(b.direction().angle_from(a.direction()))).collect()
     }
 }
+
+impl From<Path> for PathSnapshot {
+    fn from(path: Path) -> Self {
+        Self { direction: path.starting_direction(), pattern: path.pattern() }
+    }
+}
+
+impl TryFrom<PathSnapshot> for Path {
+    type Error = HexError;
+
+    fn try_from(PathSnapshot { direction, pattern }: PathSnapshot) -> HexResult<Self> {
+        Self::from_pattern(direction, &pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(ceiling: Ceiling) -> PathLimits {
+        PathLimits::new(100.into(), ceiling, FractionPolicy::default(), Objective::default())
+    }
+
+    #[test]
+    fn serializes_as_direction_and_pattern() {
+        let path = Path::from_pattern(Direction::SouthEast, "aqaaqeaa").unwrap();
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(json, r#"{"direction":"SOUTH_EAST","pattern":"aqaaqeaa"}"#);
+
+        let decoded: Path = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.value(), 60.into());
+        assert_eq!(decoded.len(), path.len());
+        assert_eq!(decoded.bounds(), path.bounds());
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(serde_json::from_str::<Path>(r#"{"direction":"SOUTH_EAST","pattern":"qaq"}"#).is_err());
+        assert!(serde_json::from_str::<Path>(r#"{"direction":"SOUTH_EAST","pattern":"aqaax"}"#).is_err());
+        assert!(serde_json::from_str::<Path>(r#"{"direction":"UP","pattern":"aqaa"}"#).is_err());
+    }
+
+    #[test]
+    fn estimate_handles_precise_targets() {
+        // the exact value of the double 0.1, which overflows the denominator if it's halved
+        let target = Ratio::new(3602879701896397, 1 << 55);
+        let limits = PathLimits::new(target, Ceiling::Unbounded, FractionPolicy::from(true), Objective::default());
+        assert_eq!(limits.estimate_steps(Ratio::new(3602879701896397, 1 << 55)), 0);
+        assert_eq!(limits.estimate_steps(1.into()), 4);
+        assert!(limits.estimate_steps(Ratio::new(1, 1 << 62)) > 0);
+    }
+
+    #[test]
+    fn estimate_counts_doublings_and_halvings() {
+        let limits = limits(Ceiling::Unbounded);
+        assert_eq!(limits.estimate_steps(100.into()), 0);
+        assert_eq!(limits.estimate_steps(50.into()), 0);
+        assert_eq!(limits.estimate_steps(25.into()), 1);
+        assert_eq!(limits.estimate_steps(400.into()), 2);
+    }
+
+    #[test]
+    fn must_add_only_under_a_ceiling() {
+        let tight = limits(Ceiling::default());
+        assert!(tight.must_add(60.into()));
+        assert!(!tight.must_add(50.into()));
+        assert!(!tight.must_add(100.into()));
+        assert!(!limits(Ceiling::Unbounded).must_add(60.into()));
+        assert!(!limits(Ceiling::Multiple(2.into())).must_add(60.into()));
+    }
+
+    #[test]
+    fn penalty_grows_with_the_remainder() {
+        let tight = limits(Ceiling::default());
+        assert_eq!(tight.ceiling_penalty(50.into()), 0);
+        assert_eq!(tight.ceiling_penalty(95.into()), 1);
+        assert_eq!(tight.ceiling_penalty(60.into()), 5);
+        assert_eq!(limits(Ceiling::Unbounded).ceiling_penalty(60.into()), 0);
+    }
+
+    #[test]
+    fn ceiling_rejects_larger_values() {
+        let tight = limits(Ceiling::default());
+        assert!(tight.test_value(100.into()).is_ok());
+        assert!(tight.test_value(120.into()).is_err());
+        assert!(limits(Ceiling::Unbounded).test_value(120.into()).is_ok());
+    }
+}
@@ -0,0 +1,119 @@
+//! Shared implementations of `to_json`, `from_json` and `__reduce__` for pyclasses, since pyo3 only allows one
+//! `#[pymethods]` block per class. Pickled objects are stored as JSON and rebuilt with the class's `from_json`, so the
+//! class must set `module = "hexnumgen"` for pickle to find it again.
+
+use pyo3::{prelude::*, PyClass};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::errors::HexResult;
+
+/// How a pyclass is converted to and from JSON for `to_json`, `from_json` and pickling. The defaults use serde as is.
+pub trait JsonPickle: Serialize + DeserializeOwned {
+    fn from_json(json: &str) -> HexResult<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    fn to_json(&self) -> HexResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+pub fn reduce<T>(slf: &PyCell<T>) -> PyResult<(PyObject, (String,))>
+where
+    T: PyClass + JsonPickle,
+{
+    let py = slf.py();
+    let from_json = slf.get_type().getattr("from_json")?;
+    Ok((from_json.into_py(py), (slf.borrow().to_json()?,)))
+}
+
+/// Wraps a pyclass's `#[pymethods]` block, adding `to_json`, `from_json` and `__reduce__` from its [`JsonPickle`] impl.
+///
+/// ```ignore
+/// pickle::pymethods! {
+///     impl Bounds {
+///         #[new]
+///         pub fn new(q: u32, r: u32, s: u32) -> Self {
+///             Self { q, r, s }
+///         }
+///     }
+/// }
+///
+/// impl pickle::JsonPickle for Bounds {}
+/// ```
+macro_rules! pymethods {
+    (impl $name:ident { $($body:tt)* }) => {
+        #[pyo3::pymethods]
+        impl $name {
+            $($body)*
+
+            #[classmethod]
+            #[pyo3(name = "from_json")]
+            fn from_json_py(_cls: &pyo3::types::PyType, json: &str) -> pyo3::PyResult<Self> {
+                Ok(<Self as $crate::pickle::JsonPickle>::from_json(json)?)
+            }
+
+            #[pyo3(name = "to_json")]
+            fn json(&self) -> pyo3::PyResult<String> {
+                Ok($crate::pickle::JsonPickle::to_json(self)?)
+            }
+
+            fn __reduce__(slf: &pyo3::PyCell<Self>) -> pyo3::PyResult<(pyo3::PyObject, (String,))> {
+                $crate::pickle::reduce(slf)
+            }
+        }
+    };
+}
+
+pub(crate) use pymethods;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        numgen::{
+            generators::{BeamOptions, NamedOptions},
+            Bounds, FractionPolicy, Objective,
+        },
+        GeneratedNumber, GenerationRequest, GeneratorOptions,
+    };
+
+    fn round_trip<T: JsonPickle>(value: &T) -> String {
+        let json = value.to_json().unwrap();
+        assert_eq!(T::from_json(&json).unwrap().to_json().unwrap(), json);
+        json
+    }
+
+    #[test]
+    fn round_trips_pyclasses() {
+        assert_eq!(round_trip(&Bounds::new(1, 2, 3)), r#"{"q":1,"r":2,"s":3}"#);
+        assert_eq!(round_trip(&Objective::LargestDimension), r#""largest_dimension""#);
+        round_trip(&FractionPolicy::new(true, false, Some(8)));
+        round_trip(&BeamOptions { bounds: Bounds::from(4), carryover: 10 });
+
+        let named: NamedOptions = serde_json::from_str(r#"{"name":"beam","carryover":5}"#).unwrap();
+        assert_eq!(round_trip(&named), r#"{"name":"beam","carryover":5}"#);
+
+        let number = GeneratedNumber {
+            direction: "SOUTH_EAST".into(),
+            pattern: "aqaaw".into(),
+            bounds: Bounds::from(3),
+            num_points: 5,
+            num_segments: 6,
+        };
+        round_trip(&number);
+    }
+
+    #[test]
+    fn requests_are_validated() {
+        let request = GenerationRequest::new(
+            12.into(),
+            GeneratorOptions::Beam(BeamOptions { bounds: Bounds::default(), carryover: 25 }),
+        );
+        round_trip(&request);
+
+        let invalid = r#"{"target": "1/3", "algorithm": {"name": "astar"}}"#;
+        assert!(<GenerationRequest as JsonPickle>::from_json(invalid).is_err());
+        assert!(<Bounds as JsonPickle>::from_json(r#"{"q": 1}"#).is_err());
+    }
+}
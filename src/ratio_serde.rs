@@ -0,0 +1,38 @@
+//! Serializes ratios as integers where possible, and as strings like `"1/4"` otherwise, so they stay readable in
+//! config files. Use with `#[serde(with = "crate::ratio_serde")]`.
+
+use std::{fmt::Display, str::FromStr};
+
+use num_integer::Integer;
+use num_rational::Ratio;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawRatio<T> {
+    Int(T),
+    Str(String),
+}
+
+pub fn serialize<T, S>(value: &Ratio<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Clone + Integer + Display + Serialize,
+    S: Serializer,
+{
+    if value.is_integer() {
+        value.numer().serialize(serializer)
+    } else {
+        serializer.collect_str(value)
+    }
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Ratio<T>, D::Error>
+where
+    T: Clone + Integer + FromStr + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    match RawRatio::deserialize(deserializer)? {
+        RawRatio::Int(n) => Ok(Ratio::from_integer(n)),
+        RawRatio::Str(s) => s.trim().parse().map_err(|_| D::Error::custom(format!("invalid ratio `{s}`"))),
+    }
+}
@@ -0,0 +1,59 @@
+//! Draws patterns as ASCII art on a hex grid.
+
+use std::collections::HashMap;
+
+use crate::{
+    errors::HexResult,
+    hex_math::{get_pattern_segments, Coord, Direction},
+};
+
+/// Draws a pattern as ASCII art, with points as `o`, the start as `S` and the end as `E`, eg. `aqaa`:
+///
+/// ```text
+///   o
+///  / \
+/// S---E
+///  \ /
+///   o
+/// ```
+///
+/// Each point is drawn 4 columns from its east/west neighbours and 2 rows from its diagonal ones, so the edges line up.
+pub fn render_pattern(direction: Direction, pattern: &str) -> HexResult<String> {
+    let segments = get_pattern_segments(direction, pattern)?;
+
+    // axial coordinates to (column, row), with rows increasing downwards like the r axis
+    let position = |coord: Coord| (2 * (2 * coord.q() + coord.r()), 2 * coord.r());
+
+    let mut cells: HashMap<(i32, i32), char> = HashMap::new();
+    for segment in &segments {
+        let (x1, y1) = position(segment.root());
+        let (x2, y2) = position(segment.end());
+        let edge = match (x2 - x1, y2 - y1) {
+            (_, 0) => '-',
+            (dx, dy) if dx.signum() == dy.signum() => '\\',
+            _ => '/',
+        };
+        if y1 == y2 {
+            for x in x1.min(x2) + 1..x1.max(x2) {
+                cells.insert((x, y1), edge);
+            }
+        } else {
+            cells.insert(((x1 + x2) / 2, (y1 + y2) / 2), edge);
+        }
+        cells.entry((x1, y1)).or_insert('o');
+        cells.insert((x2, y2), 'o');
+    }
+    cells.insert(position(segments[0].root()), 'S');
+    cells.insert(position(segments[segments.len() - 1].end()), 'E');
+
+    let min_x = cells.keys().map(|&(x, _)| x).min().unwrap_or_default();
+    let min_y = cells.keys().map(|&(_, y)| y).min().unwrap_or_default();
+    let max_y = cells.keys().map(|&(_, y)| y).max().unwrap_or_default();
+
+    let mut lines = Vec::new();
+    for y in min_y..=max_y {
+        let max_x = cells.keys().filter(|&&(_, row)| row == y).map(|&(x, _)| x).max().unwrap_or(min_x - 1);
+        lines.push((min_x..=max_x).map(|x| cells.get(&(x, y)).copied().unwrap_or(' ')).collect::<String>());
+    }
+    Ok(lines.join("\n"))
+}
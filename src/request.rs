@@ -0,0 +1,377 @@
+use clap::Args;
+use num_rational::Ratio;
+use pyo3::{prelude::*, types::PyType};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check_target,
+    errors::{HexError, HexResult},
+    non_negative,
+    numgen::{Ceiling, FractionPolicy, Objective, PathLimits, SearchHooks},
+    parse_expression, pickle, signals, GeneratedNumber, GeneratorOptions, PyCeiling, PyFractionPolicy, PyRatio,
+};
+
+/// Everything needed to generate a number: the target, which paths are allowed on the way there, what makes one
+/// pattern better than another, and which algorithm to search with.
+///
+/// Serializes to the same format whether it's loaded from JSON or TOML, eg.
+///
+/// ```toml
+/// target = "5/4"
+/// objective = "segments"
+/// ceiling = { multiple = 2 }
+///
+/// [fractions]
+/// fractional_target = true
+/// fractional_intermediates = true
+///
+/// [algorithm]
+/// name = "beam-split"
+/// carryover = 50
+/// num_threads = 4
+/// ```
+#[pyclass(module = "hexnumgen")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenerationRequest {
+    #[serde(with = "crate::ratio_serde")]
+    pub target: Ratio<i64>,
+    #[serde(default)]
+    pub objective: Objective,
+    #[serde(default)]
+    pub ceiling: Ceiling,
+    #[serde(default)]
+    pub fractions: FractionPolicy,
+    pub algorithm: GeneratorOptions,
+}
+
+impl GenerationRequest {
+    pub fn new(target: Ratio<i64>, algorithm: GeneratorOptions) -> Self {
+        Self {
+            target,
+            objective: Objective::default(),
+            ceiling: Ceiling::default(),
+            fractions: FractionPolicy::default(),
+            algorithm,
+        }
+    }
+
+    pub fn with_ceiling(self, ceiling: Ceiling) -> Self {
+        Self { ceiling, ..self }
+    }
+
+    pub fn with_fractions(self, fractions: FractionPolicy) -> Self {
+        Self { fractions, ..self }
+    }
+
+    pub fn with_objective(self, objective: Objective) -> Self {
+        Self { objective, ..self }
+    }
+
+    pub fn limits(&self) -> PathLimits {
+        PathLimits::new(self.target, self.ceiling, self.fractions, self.objective)
+    }
+
+    /// Checks that the target is reachable and the algorithm's options make sense, without running the search.
+    pub fn validate(&self) -> HexResult<()> {
+        check_target(self.target, self.ceiling, self.fractions)?;
+        self.algorithm.validate()
+    }
+
+    pub fn generate(&self) -> HexResult<Option<GeneratedNumber>> {
+        self.generate_with_hooks(SearchHooks::default())
+    }
+
+    /// Like [`GenerationRequest::generate`], but calls `hooks` as the search progresses.
+    pub fn generate_with_hooks(&self, hooks: SearchHooks) -> HexResult<Option<GeneratedNumber>> {
+        self.validate()?;
+        Ok(self.algorithm.clone().run(self.target, self.limits().with_hooks(hooks))?.map(Into::into))
+    }
+
+    pub fn from_json(json: &str) -> HexResult<Self> {
+        let request: Self = serde_json::from_str(json).map_err(|err| HexError::InvalidConfig(err.to_string()))?;
+        request.validate()?;
+        Ok(request)
+    }
+
+    pub fn to_json(&self) -> HexResult<String> {
+        serde_json::to_string_pretty(self).map_err(|err| HexError::InvalidConfig(err.to_string()))
+    }
+
+    pub fn from_toml(toml: &str) -> HexResult<Self> {
+        let request: Self = toml::from_str(toml).map_err(|err| HexError::InvalidConfig(err.to_string()))?;
+        request.validate()?;
+        Ok(request)
+    }
+
+    pub fn to_toml(&self) -> HexResult<String> {
+        toml::to_string(self).map_err(|err| HexError::InvalidConfig(err.to_string()))
+    }
+}
+
+pickle::pymethods! {
+    impl GenerationRequest {
+        #[new]
+        #[pyo3(signature = (target, algorithm, ceiling=None, fractions=None, objective=None))]
+        fn py_new(
+            target: PyRatio,
+            algorithm: GeneratorOptions,
+            ceiling: Option<PyCeiling>,
+            fractions: Option<PyFractionPolicy>,
+            objective: Option<Objective>,
+        ) -> PyResult<Self> {
+            let request = Self {
+                target: target.try_into()?,
+                objective: objective.unwrap_or_default(),
+                ceiling: ceiling.map(TryInto::try_into).transpose()?.unwrap_or_default(),
+                fractions: fractions.map(Into::into).unwrap_or_default(),
+                algorithm,
+            };
+            request.validate()?;
+            Ok(request)
+        }
+
+        #[classmethod]
+        #[pyo3(name = "from_toml")]
+        fn from_toml_py(_cls: &PyType, toml: &str) -> PyResult<Self> {
+            Ok(Self::from_toml(toml)?)
+        }
+
+        #[pyo3(name = "to_toml")]
+        fn to_toml_py(&self) -> PyResult<String> {
+            Ok(self.to_toml()?)
+        }
+
+        #[pyo3(name = "validate")]
+        fn validate_py(&self) -> PyResult<()> {
+            Ok(self.validate()?)
+        }
+
+        #[pyo3(name = "generate")]
+        fn generate_py(&self, py: Python) -> PyResult<Option<GeneratedNumber>> {
+            Ok(signals::allow_threads(py, |hooks| self.generate_with_hooks(hooks))??)
+        }
+
+        fn __repr__(&self) -> String {
+            format!("{self:?}")
+        }
+    }
+}
+
+// validates the request, and pretty prints it since it's meant to be edited by hand
+impl pickle::JsonPickle for GenerationRequest {
+    fn from_json(json: &str) -> HexResult<Self> {
+        GenerationRequest::from_json(json)
+    }
+
+    fn to_json(&self) -> HexResult<String> {
+        GenerationRequest::to_json(self)
+    }
+}
+
+/// Command line flags for the ceiling, fractions and objective of a [`GenerationRequest`], shared by the binaries.
+#[derive(Debug, Clone, Default, Args)]
+pub struct LimitArgs {
+    /// Keep generated paths larger than the target value instead of discarding them (paths generate slower but may be
+    /// more compact). pregen keeps them unless --max-multiple or --max-value is given
+    #[arg(short, long, group = "ceiling")]
+    pub keep_larger: bool,
+
+    /// Discard paths larger than this multiple of the target value
+    #[arg(long, group = "ceiling", value_parser = parse_expression)]
+    pub max_multiple: Option<Ratio<i64>>,
+
+    /// Discard paths larger than this value
+    #[arg(long, group = "ceiling", value_parser = parse_expression)]
+    pub max_value: Option<Ratio<i64>>,
+
+    /// If fractional targets and intermediate values should be allowed (shorthand for --fractional-target and
+    /// --fractional-intermediates)
+    #[arg(short, long, default_value_t = false)]
+    pub fractions: bool,
+
+    #[command(flatten)]
+    pub fraction_policy: FractionPolicy,
+
+    /// What makes one pattern better than another
+    #[arg(long, value_enum)]
+    pub objective: Option<Objective>,
+}
+
+impl LimitArgs {
+    pub fn objective(&self) -> Objective {
+        self.objective.unwrap_or_default()
+    }
+
+    /// The ceiling chosen by the flags, or `default` if none of them were given.
+    pub fn ceiling(&self, default: Ceiling) -> HexResult<Ceiling> {
+        Ok(match (self.keep_larger, self.max_multiple, self.max_value) {
+            (true, _, _) => Ceiling::Unbounded,
+            (_, Some(multiple), _) => Ceiling::Multiple(non_negative(multiple)?),
+            (_, _, Some(max_value)) => Ceiling::Absolute(non_negative(max_value)?),
+            _ => default,
+        })
+    }
+
+    pub fn fractions(&self) -> FractionPolicy {
+        let mut fractions = self.fraction_policy;
+        if self.fractions {
+            fractions.fractional_target = true;
+            fractions.fractional_intermediates = true;
+        }
+        fractions
+    }
+
+    /// Replaces the limits of `request` with these, using `default_ceiling` if no ceiling flag was given.
+    pub fn apply(&self, request: GenerationRequest, default_ceiling: Ceiling) -> HexResult<GenerationRequest> {
+        Ok(request
+            .with_ceiling(self.ceiling(default_ceiling)?)
+            .with_fractions(self.fractions())
+            .with_objective(self.objective()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::numgen::{
+        generators::{AStarOptions, BeamSplitOptions, NamedOptions},
+        Path,
+    };
+
+    const TOML: &str = r#"
+target = "5/4"
+objective = "segments"
+ceiling = { multiple = 2 }
+
+[fractions]
+fractional_target = true
+fractional_intermediates = true
+
+[algorithm]
+name = "beam-split"
+carryover = 50
+num_threads = 4
+"#;
+
+    #[test]
+    fn loads_toml() {
+        let request = GenerationRequest::from_toml(TOML).unwrap();
+        assert_eq!(request.target, Ratio::new(5, 4));
+        assert_eq!(request.objective, Objective::Segments);
+        assert_eq!(request.ceiling, Ceiling::Multiple(2.into()));
+        assert_eq!(request.fractions, FractionPolicy::new(true, true, None));
+        assert!(matches!(
+            request.algorithm,
+            GeneratorOptions::BeamSplit(BeamSplitOptions { carryover: 50, num_threads: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn round_trips_through_json_and_toml() {
+        let request = GenerationRequest::from_toml(TOML).unwrap();
+        let from_json = GenerationRequest::from_json(&request.to_json().unwrap()).unwrap();
+        let from_toml = GenerationRequest::from_toml(&request.to_toml().unwrap()).unwrap();
+        assert_eq!(from_json.to_json().unwrap(), request.to_json().unwrap());
+        assert_eq!(from_toml.to_json().unwrap(), request.to_json().unwrap());
+    }
+
+    #[test]
+    fn defaults_everything_but_target_and_algorithm() {
+        let request = GenerationRequest::from_json(r#"{"target": 12, "algorithm": {"name": "astar"}}"#).unwrap();
+        assert_eq!(request.objective, Objective::default());
+        assert_eq!(request.ceiling, Ceiling::default());
+        assert_eq!(request.fractions, FractionPolicy::default());
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        // unknown fields
+        assert!(GenerationRequest::from_json(r#"{"target": 1, "algorithm": {"name": "astar"}, "extra": 1}"#).is_err());
+        // unreachable targets are caught when loading, not when generating
+        assert!(matches!(
+            GenerationRequest::from_json(r#"{"target": "1/3", "algorithm": {"name": "astar"}}"#),
+            Err(HexError::FractionalTarget(_))
+        ));
+        assert!(GenerationRequest::from_toml("target = 5").is_err());
+    }
+
+    #[test]
+    fn limits_use_the_request_settings() {
+        let request = GenerationRequest::new(
+            40.into(),
+            GeneratorOptions::Named(NamedOptions { name: "astar".into(), params: Default::default() }),
+        )
+        .with_ceiling(Ceiling::Absolute(50.into()))
+        .with_objective(Objective::Segments);
+        let limits = request.limits();
+        assert_eq!(limits.target, 40.into());
+        assert_eq!(limits.max_value, Some(50.into()));
+        assert_eq!(limits.objective, Objective::Segments);
+    }
+
+    #[test]
+    fn cancelled_searches_stop_early() {
+        for name in ["beam", "beam-pool", "beam-split", "astar", "astar-split"] {
+            let algorithm = serde_json::from_value(serde_json::json!({ "name": name, "num_threads": 2 })).unwrap();
+            let request = GenerationRequest::new(1000.into(), algorithm);
+
+            // cancelled before it starts, so nothing is found
+            let hooks = SearchHooks::default().cancel_on(Arc::new(AtomicBool::new(true)));
+            assert!(request.generate_with_hooks(hooks).unwrap().is_none(), "{name}");
+
+            // cancelled by the first improvement, so that's what's returned
+            let cancelled = Arc::new(AtomicBool::new(false));
+            let hooks = SearchHooks::default().cancel_on(cancelled.clone()).on_improvement({
+                let cancelled = cancelled.clone();
+                move |_| cancelled.store(true, Ordering::Relaxed)
+            });
+            let number = request.generate_with_hooks(hooks).unwrap().unwrap();
+            assert!(cancelled.load(Ordering::Relaxed), "{name}");
+            let path = Path::from_pattern(number.direction.parse().unwrap(), &number.pattern).unwrap();
+            assert_eq!(path.value(), 1000.into(), "{name}");
+        }
+    }
+
+    #[derive(Parser)]
+    struct Wrapper {
+        #[command(flatten)]
+        limits: LimitArgs,
+    }
+
+    fn parse(args: &[&str]) -> LimitArgs {
+        Wrapper::parse_from(["test"].iter().chain(args)).limits
+    }
+
+    #[test]
+    fn limit_args_choose_the_ceiling() {
+        assert_eq!(parse(&[]).ceiling(Ceiling::Unbounded).unwrap(), Ceiling::Unbounded);
+        assert_eq!(parse(&[]).ceiling(Ceiling::default()).unwrap(), Ceiling::default());
+        assert_eq!(parse(&["-k"]).ceiling(Ceiling::default()).unwrap(), Ceiling::Unbounded);
+        assert_eq!(
+            parse(&["--max-multiple", "3/2"]).ceiling(Ceiling::Unbounded).unwrap(),
+            Ceiling::Multiple(Ratio::new(3, 2))
+        );
+        assert_eq!(parse(&["--max-value", "2^4"]).ceiling(Ceiling::Unbounded).unwrap(), Ceiling::Absolute(16.into()));
+        assert!(matches!(parse(&["--max-value=-1"]).ceiling(Ceiling::Unbounded), Err(HexError::Negative(_))));
+        assert!(Wrapper::try_parse_from(["test", "-k", "--max-value", "4"]).is_err());
+    }
+
+    #[test]
+    fn limit_args_apply_to_requests() {
+        let request = GenerationRequest::new(5.into(), GeneratorOptions::AStar(AStarOptions {}));
+        let request = parse(&["-f", "--max-denominator", "4", "--objective", "segments"])
+            .apply(request, Ceiling::Unbounded)
+            .unwrap();
+        assert_eq!(request.ceiling, Ceiling::Unbounded);
+        assert_eq!(request.fractions, FractionPolicy::new(true, true, Some(4)));
+        assert_eq!(request.objective, Objective::Segments);
+    }
+}
@@ -0,0 +1,71 @@
+//! Runs searches from Python without holding the GIL, while still letting Ctrl-C stop them. Python only runs signal
+//! handlers on its main thread, so the search runs on a separate thread and the calling thread checks for signals while
+//! it waits.
+
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+
+use crate::numgen::SearchHooks;
+
+/// How often to check for signals, which is roughly how long Ctrl-C takes to stop a search.
+const SIGNAL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Runs `f` with the GIL released, passing it hooks that cancel the search if a signal handler raises (eg.
+/// `KeyboardInterrupt` on Ctrl-C). The exception is returned once the search has stopped.
+pub fn allow_threads<T, F>(py: Python, f: F) -> PyResult<T>
+where
+    T: Send,
+    F: FnOnce(SearchHooks) -> T + Send,
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let hooks = SearchHooks::default().cancel_on(cancelled.clone());
+
+    thread::scope(|scope| {
+        let (done_tx, done_rx) = mpsc::channel();
+        let search = scope.spawn(move || {
+            let result = f(hooks);
+            // nothing is waiting anymore if the search was interrupted
+            done_tx.send(()).ok();
+            result
+        });
+
+        // a message means it finished, and a closed channel means it panicked
+        let interrupted = recv(py, &Mutex::new(done_rx), &cancelled).err();
+
+        // a cancelled search stops at its next step, so this doesn't wait long
+        let result = search.join().unwrap_or_else(|payload| panic::resume_unwind(payload));
+        match interrupted {
+            Some(err) => Err(err),
+            None => Ok(result),
+        }
+    })
+}
+
+/// Waits for a message from searches running on other threads with the GIL released, or returns `None` once every
+/// sender is gone. If a signal handler raises while waiting, `cancelled` is set to stop the searches and the exception
+/// is returned.
+pub fn recv<T: Send>(py: Python, receiver: &Mutex<Receiver<T>>, cancelled: &AtomicBool) -> PyResult<Option<T>> {
+    py.allow_threads(|| loop {
+        match receiver.lock().recv_timeout(SIGNAL_INTERVAL) {
+            Ok(message) => return Ok(Some(message)),
+            Err(RecvTimeoutError::Disconnected) => return Ok(None),
+            Err(RecvTimeoutError::Timeout) => {
+                if let Err(err) = Python::with_gil(|py| py.check_signals()) {
+                    cancelled.store(true, Ordering::Relaxed);
+                    return Err(err);
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,371 @@
+//! Compact binary tables. Every number is little-endian, and the file is laid out as:
+//!
+//! - the magic bytes `HEXNUMS\0` and a `u32` version
+//! - since version 2, the `u64` length of the metadata, then the metadata as JSON (or nothing if the length is 0)
+//! - the step between neighbouring targets, as an `i64` numerator and denominator
+//! - a `u64` number of runs, then for each run of evenly spaced targets, its first target as an `i64` numerator and
+//!   denominator, and the `u64` index of its first entry
+//! - a `u64` number of entries, then a byte for each entry with its starting direction, or 255 if it failed
+//! - the `u32` index of each entry's first angle, followed by the total number of angles
+//! - every angle of every pattern in 3 bits, starting from the least significant bit of the first byte
+//!
+//! Tables from `pregen` have one run of targets, or two if they skip the ones near zero, so finding an entry is just a
+//! division and a few reads.
+
+use std::io::{BufRead, Read, Write};
+
+use num_integer::Integer;
+use num_rational::Ratio;
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, ToPrimitive};
+
+use super::{TableEntry, TableMetadata};
+use crate::{
+    errors::{HexError, HexResult},
+    hex_math::{Angle, Direction},
+};
+
+const MAGIC: &[u8; 8] = b"HEXNUMS\0";
+const VERSION: u32 = 2;
+/// Version 1 tables have no metadata, but are otherwise the same
+const VERSION_WITHOUT_METADATA: u32 = 1;
+const FAILED: u8 = 255;
+
+/// Checks if a reader holds a binary table without consuming anything.
+pub(super) fn has_magic<R: BufRead>(reader: &mut R) -> HexResult<bool> {
+    Ok(reader.fill_buf()?.starts_with(MAGIC))
+}
+
+/// Writes entries, which must already be sorted by target.
+pub(super) fn write<W: Write>(
+    mut writer: W,
+    entries: &[&TableEntry],
+    metadata: Option<&TableMetadata>,
+) -> HexResult<()> {
+    let targets: Vec<_> = entries.iter().map(|entry| entry.target).collect();
+    let step = common_step(&targets)?;
+
+    let mut runs = Vec::new();
+    for (i, target) in targets.iter().enumerate() {
+        if i == 0 || target.checked_sub(&targets[i - 1]) != Some(step) {
+            runs.push((*target, i as u64));
+        }
+    }
+
+    let mut directions = Vec::with_capacity(entries.len());
+    let mut offsets = Vec::with_capacity(entries.len() + 1);
+    let mut angles = AnglePacker::default();
+    for entry in entries {
+        offsets.push(angles.len()?);
+        match &entry.pattern {
+            Some((direction, pattern)) => {
+                directions.push(*direction as u8);
+                for c in pattern.chars() {
+                    angles.push(Angle::try_from(c)?);
+                }
+            }
+            None => directions.push(FAILED),
+        }
+    }
+    offsets.push(angles.len()?);
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    let metadata = metadata.map(serde_json::to_vec).transpose()?.unwrap_or_default();
+    writer.write_all(&(metadata.len() as u64).to_le_bytes())?;
+    writer.write_all(&metadata)?;
+    write_ratio(&mut writer, step)?;
+    writer.write_all(&(runs.len() as u64).to_le_bytes())?;
+    for (start, first) in runs {
+        write_ratio(&mut writer, start)?;
+        writer.write_all(&first.to_le_bytes())?;
+    }
+    writer.write_all(&(entries.len() as u64).to_le_bytes())?;
+    writer.write_all(&directions)?;
+    for offset in offsets {
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+    writer.write_all(&angles.finish())?;
+    Ok(writer.flush()?)
+}
+
+/// The largest step that every target is a multiple of, offset from the first one.
+fn common_step(targets: &[Ratio<i64>]) -> HexResult<Ratio<i64>> {
+    let mut step: Option<Ratio<i64>> = None;
+    for pair in targets.windows(2) {
+        let difference = pair[1].checked_sub(&pair[0]).ok_or(HexError::Overflow)?;
+        step = Some(match step {
+            // gcd(a/b, c/d) = gcd(ad, cb) / bd
+            Some(step) => {
+                let numer = step.numer().checked_mul(difference.denom()).ok_or(HexError::Overflow)?;
+                let other = difference.numer().checked_mul(step.denom()).ok_or(HexError::Overflow)?;
+                let denom = step.denom().checked_mul(difference.denom()).ok_or(HexError::Overflow)?;
+                Ratio::new(numer.gcd(&other), denom)
+            }
+            None => difference,
+        });
+    }
+    Ok(step.unwrap_or_else(Ratio::one))
+}
+
+fn write_ratio<W: Write>(writer: &mut W, value: Ratio<i64>) -> HexResult<()> {
+    writer.write_all(&value.numer().to_le_bytes())?;
+    Ok(writer.write_all(&value.denom().to_le_bytes())?)
+}
+
+#[derive(Default)]
+struct AnglePacker {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+    count: u64,
+}
+
+impl AnglePacker {
+    fn push(&mut self, angle: Angle) {
+        self.buffer |= (angle as u32) << self.bits;
+        self.bits += 3;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+        self.count += 1;
+    }
+
+    fn len(&self) -> HexResult<u32> {
+        self.count.try_into().map_err(|_| HexError::InvalidTable("too many angles for a binary table".into()))
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// A binary table held in memory, which decodes entries when they're requested.
+#[derive(Debug)]
+pub(super) struct BinaryTable {
+    bytes: Vec<u8>,
+    step: Ratio<i64>,
+    /// First target and entry index of each run
+    runs: Vec<(Ratio<i64>, usize)>,
+    len: usize,
+    directions_start: usize,
+    offsets_start: usize,
+    angles_start: usize,
+}
+
+impl BinaryTable {
+    pub fn read<R: Read>(mut reader: R) -> HexResult<(Self, Option<TableMetadata>)> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let mut cursor = Cursor { bytes: &bytes, position: 0 };
+        if cursor.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a binary table"));
+        }
+        let metadata = match cursor.u32()? {
+            VERSION => match cursor.u64()? as usize {
+                0 => None,
+                len => Some(
+                    serde_json::from_slice(cursor.take(len)?)
+                        .map_err(|err| invalid(format!("invalid metadata: {err}")))?,
+                ),
+            },
+            VERSION_WITHOUT_METADATA => None,
+            version => return Err(invalid(format!("unsupported version {version}"))),
+        };
+
+        let step = cursor.ratio()?;
+        if step <= Ratio::from_integer(0) {
+            return Err(invalid("step must be positive"));
+        }
+        let num_runs = cursor.u64()?;
+        let mut runs = Vec::new();
+        for _ in 0..num_runs {
+            let start = cursor.ratio()?;
+            let first = cursor.u64()? as usize;
+            if runs.last().is_some_and(|&(last_start, last_first)| start <= last_start || first <= last_first) {
+                return Err(invalid("runs must be in order"));
+            }
+            runs.push((start, first));
+        }
+
+        let len = cursor.u64()? as usize;
+        if runs.first().map_or(len > 0, |&(_, first)| first != 0) || runs.last().is_some_and(|&(_, first)| first >= len)
+        {
+            return Err(invalid("runs don't cover every entry"));
+        }
+        let directions_start = cursor.position;
+        cursor.take(len)?;
+        let offsets_start = cursor.position;
+        cursor.take(len.checked_add(1).and_then(|n| n.checked_mul(4)).ok_or_else(|| invalid("too many entries"))?)?;
+        let angles_start = cursor.position;
+
+        let table = Self { bytes, step, runs, len, directions_start, offsets_start, angles_start };
+        if (0..len).any(|index| table.offset(index) > table.offset(index + 1)) {
+            return Err(invalid("angle offsets must be in order"));
+        }
+        let total_angles = table.offset(len) as usize;
+        if table.bytes.len() < angles_start + (total_angles * 3 + 7) / 8 {
+            return Err(invalid("truncated angles"));
+        }
+        Ok((table, metadata))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Finds the index of `target`'s entry, if it's in the table.
+    pub fn index_of(&self, target: Ratio<i64>) -> Option<usize> {
+        let run = self.runs.partition_point(|&(start, _)| start <= target).checked_sub(1)?;
+        let (start, first) = self.runs[run];
+        let steps = target.checked_sub(&start)?.checked_div(&self.step)?;
+        if !steps.is_integer() {
+            return None;
+        }
+
+        let index = first.checked_add(steps.to_integer().to_usize()?)?;
+        let end = self.runs.get(run + 1).map_or(self.len, |&(_, first)| first);
+        (index < end).then_some(index)
+    }
+
+    pub fn entry(&self, index: usize) -> HexResult<TableEntry> {
+        let run = self.runs.partition_point(|&(_, first)| first <= index) - 1;
+        let (start, first) = self.runs[run];
+        let target = i64::try_from(index - first)
+            .ok()
+            .and_then(|steps| self.step.checked_mul(&steps.into()))
+            .and_then(|offset| start.checked_add(&offset))
+            .ok_or(HexError::Overflow)?;
+
+        let pattern = match self.bytes[self.directions_start + index] {
+            FAILED => None,
+            direction @ 0..=5 => {
+                let angles = (self.offset(index)..self.offset(index + 1))
+                    .map(|i| self.angle(i as usize).map(char::from))
+                    .collect::<HexResult<String>>()?;
+                Some((Direction::from(direction as i32), angles))
+            }
+            direction => return Err(invalid(format!("invalid direction {direction} for `{target}`"))),
+        };
+        Ok(TableEntry::new(target, pattern))
+    }
+
+    fn offset(&self, index: usize) -> u32 {
+        let start = self.offsets_start + index * 4;
+        u32::from_le_bytes(self.bytes[start..start + 4].try_into().unwrap())
+    }
+
+    fn angle(&self, i: usize) -> HexResult<Angle> {
+        let bit = i * 3;
+        let byte = self.angles_start + bit / 8;
+        let word = self.bytes[byte] as u16 | (self.bytes.get(byte + 1).copied().unwrap_or_default() as u16) << 8;
+        match (word >> (bit % 8)) & 0b111 {
+            angle @ 0..=5 => Ok(Angle::from(angle as i32)),
+            angle => Err(invalid(format!("invalid angle {angle}"))),
+        }
+    }
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> HexResult<&'a [u8]> {
+        let end =
+            self.position.checked_add(n).filter(|&end| end <= self.bytes.len()).ok_or_else(|| invalid("truncated"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> HexResult<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> HexResult<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn ratio(&mut self) -> HexResult<Ratio<i64>> {
+        let numer = i64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        let denom = i64::from_le_bytes(self.take(8)?.try_into().unwrap());
+        if denom == 0 {
+            return Err(invalid("zero denominator"));
+        }
+        // reduce in 128 bits, since eg. `1/i64::MIN` can't be negated to make the denominator positive
+        let ratio = Ratio::new(i128::from(numer), i128::from(denom));
+        match (i64::try_from(*ratio.numer()), i64::try_from(*ratio.denom())) {
+            (Ok(numer), Ok(denom)) => Ok(Ratio::new_raw(numer, denom)),
+            _ => Err(invalid(format!("`{numer}/{denom}` is out of range"))),
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> HexError {
+    HexError::InvalidTable(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_entries(targets: &[Ratio<i64>]) -> Vec<u8> {
+        let entries: Vec<_> = targets.iter().map(|&target| TableEntry::from_tail(target, Some("w"))).collect();
+        let mut bytes = Vec::new();
+        write(&mut bytes, &entries.iter().collect::<Vec<_>>(), None).unwrap();
+        bytes
+    }
+
+    /// Where the step is stored in a table without metadata: after the magic, version and metadata length.
+    const STEP: usize = 8 + 4 + 8;
+
+    #[test]
+    fn finds_targets_in_runs() {
+        let targets = [Ratio::new(-1, 2), 0.into(), Ratio::new(3, 2), 2.into(), Ratio::new(5, 2)];
+        let (table, metadata) = BinaryTable::read(write_entries(&targets).as_slice()).unwrap();
+        assert!(metadata.is_none());
+        assert_eq!(table.step, Ratio::new(1, 2));
+        assert_eq!(table.runs.len(), 2);
+        for (index, target) in targets.iter().enumerate() {
+            assert_eq!(table.index_of(*target), Some(index));
+            assert_eq!(table.entry(index).unwrap().target, *target);
+        }
+        assert_eq!(table.index_of(Ratio::new(1, 2)), None);
+        assert_eq!(table.index_of(Ratio::new(1, 4)), None);
+        assert_eq!(table.index_of(3.into()), None);
+    }
+
+    #[test]
+    fn extreme_targets_round_trip() {
+        let bytes = write_entries(&[i64::MIN.into()]);
+        let (table, _) = BinaryTable::read(bytes.as_slice()).unwrap();
+        assert_eq!(table.entry(0).unwrap().target, i64::MIN.into());
+        assert_eq!(table.index_of(i64::MIN.into()), Some(0));
+    }
+
+    #[test]
+    fn rejects_unrepresentable_ratios() {
+        let bytes = write_entries(&[0.into(), 1.into()]);
+        for (numer, denom) in [(1, i64::MIN), (i64::MIN, -1), (i64::MIN, -3), (1, 0)] {
+            let mut bytes = bytes.clone();
+            bytes[STEP..STEP + 8].copy_from_slice(&numer.to_le_bytes());
+            bytes[STEP + 8..STEP + 16].copy_from_slice(&denom.to_le_bytes());
+            assert!(matches!(BinaryTable::read(bytes.as_slice()), Err(HexError::InvalidTable(_))), "{numer}/{denom}");
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_tables() {
+        let bytes = write_entries(&[0.into(), 1.into(), 2.into()]);
+        for len in [0, 4, STEP, bytes.len() - 1] {
+            assert!(BinaryTable::read(&bytes[..len]).is_err(), "{len}");
+        }
+    }
+}
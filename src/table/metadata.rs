@@ -0,0 +1,77 @@
+//! Where a table came from, so merged and improved tables can still be traced back to the runs that made them.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    numgen::{Ceiling, FractionPolicy, Objective},
+    GenerationRequest, GeneratorOptions,
+};
+
+/// Stored at the start of a table, except JSON tables, which stay a plain map unless written with `json_metadata`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableMetadata {
+    /// Version of hexnumgen that wrote the table
+    pub version: String,
+    /// When the table was made, in seconds since the Unix epoch
+    pub created: u64,
+    /// Seconds spent generating, including the runs of any tables this one was extended, merged or improved from
+    pub runtime: f64,
+    /// Everything that generated patterns for the table, which entries refer to by index
+    pub generators: Vec<TableGenerator>,
+}
+
+impl TableMetadata {
+    /// Metadata for a table made now by this version.
+    pub fn new(generators: Vec<TableGenerator>) -> Self {
+        let created = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Self { version: env!("CARGO_PKG_VERSION").into(), created, runtime: 0.0, generators }
+    }
+
+    pub fn add_runtime(&mut self, runtime: Duration) {
+        self.runtime += runtime.as_secs_f64();
+    }
+
+    /// Returns the index of `generator`, adding it if it isn't already listed.
+    pub fn add_generator(&mut self, generator: TableGenerator) -> usize {
+        match self.generators.iter().position(|existing| existing.same_settings(&generator)) {
+            Some(index) => index,
+            None => {
+                self.generators.push(generator);
+                self.generators.len() - 1
+            }
+        }
+    }
+}
+
+/// The settings a generator searched with, ie. a [`GenerationRequest`] without its target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableGenerator {
+    pub objective: Objective,
+    pub ceiling: Ceiling,
+    pub fractions: FractionPolicy,
+    pub algorithm: GeneratorOptions,
+}
+
+impl TableGenerator {
+    /// Returns true if both searched with the same settings.
+    pub fn same_settings(&self, other: &Self) -> bool {
+        // the algorithm options don't implement PartialEq, so compare them the way they're stored
+        self.objective == other.objective
+            && self.ceiling == other.ceiling
+            && self.fractions == other.fractions
+            && serde_json::to_value(&self.algorithm).ok() == serde_json::to_value(&other.algorithm).ok()
+    }
+}
+
+impl From<&GenerationRequest> for TableGenerator {
+    fn from(request: &GenerationRequest) -> Self {
+        Self {
+            objective: request.objective,
+            ceiling: request.ceiling,
+            fractions: request.fractions,
+            algorithm: request.algorithm.clone(),
+        }
+    }
+}
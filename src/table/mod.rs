@@ -0,0 +1,476 @@
+//! Tables of pregenerated number patterns, like the ones written by `pregen`. Text tables are parsed into memory when
+//! they're opened, while binary tables stay packed and only decode the entries that are asked for.
+
+mod binary;
+mod metadata;
+mod text;
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::{Path as FsPath, PathBuf},
+};
+
+use clap::ValueEnum;
+use num_rational::Ratio;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{HexError, HexResult},
+    hex_math::Direction,
+    numgen::{Bounds, Path},
+    GeneratedNumber, PyRatio,
+};
+
+use binary::BinaryTable;
+pub use metadata::{TableGenerator, TableMetadata};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TableFormat {
+    /// One JSON object mapping each target to `[direction, pattern]`
+    Json,
+    /// One JSON object per line, so the table can be read without loading all of it
+    Jsonl,
+    /// One row per target, after a header
+    Csv,
+    /// Patterns packed into 3 bits per angle, with an index to look up targets without decoding the rest
+    Binary,
+}
+
+impl TableFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+            Self::Binary => "bin",
+        }
+    }
+
+    /// Guesses the format of a table from its file extension, defaulting to JSON.
+    pub fn from_path(path: &FsPath) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl") => Self::Jsonl,
+            Some("csv") => Self::Csv,
+            Some("bin") => Self::Binary,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Metrics of a table entry's pattern, which CSV and JSONL tables can store so consumers don't need to decode it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableMetrics {
+    pub segments: usize,
+    pub points: usize,
+    pub bounds: Bounds,
+    pub quasi_area: u32,
+}
+
+impl From<&Path> for TableMetrics {
+    fn from(path: &Path) -> Self {
+        Self {
+            segments: path.len(),
+            points: path.num_points(),
+            bounds: path.bounds(),
+            quasi_area: path.bounds().quasi_area(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableEntry {
+    pub target: Ratio<i64>,
+    /// Starting direction and pattern, or `None` if no pattern was found for the target
+    pub pattern: Option<(Direction, String)>,
+    /// Only read from tables that store metrics
+    pub metrics: Option<TableMetrics>,
+    /// Index of the generator that found the pattern in the table's [`TableMetadata::generators`], only read from
+    /// tables that store it
+    pub generator: Option<usize>,
+}
+
+impl TableEntry {
+    pub fn new(target: Ratio<i64>, pattern: Option<(Direction, String)>) -> Self {
+        Self { target, pattern, metrics: None, generator: None }
+    }
+
+    /// Builds the entry for `target` from the tail of its pattern, which is the same for positive and negative numbers.
+    pub fn from_tail(target: Ratio<i64>, tail: Option<&str>) -> Self {
+        let pattern = tail.map(|tail| match target < 0.into() {
+            true => (Direction::NorthEast, format!("dedd{tail}")),
+            false => (Direction::SouthEast, format!("aqaa{tail}")),
+        });
+        Self::new(target, pattern)
+    }
+
+    /// The pattern without its aqaa/dedd prefix.
+    pub fn tail(&self) -> Option<&str> {
+        self.pattern.as_ref().map(|(_, pattern)| pattern.get(4..).unwrap_or_default())
+    }
+
+    /// Decodes the pattern, or returns `None` if the target failed.
+    pub fn path(&self) -> HexResult<Option<Path>> {
+        self.pattern.as_ref().map(|(direction, pattern)| Path::from_pattern(*direction, pattern)).transpose()
+    }
+
+    pub fn number(&self) -> HexResult<Option<GeneratedNumber>> {
+        Ok(self.path()?.map(GeneratedNumber::from))
+    }
+
+    /// Decodes the pattern to record its metrics, for formats that can store them.
+    pub fn with_metrics(self) -> HexResult<Self> {
+        let metrics = self.path()?.as_ref().map(TableMetrics::from);
+        Ok(Self { metrics, ..self })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableWriteOptions {
+    /// Indent JSON tables
+    pub pretty: bool,
+    /// Only write the tails of the non-negative targets (JSON only)
+    pub only_tail: bool,
+    /// Write the metadata under a `metadata` key in JSON tables, which are otherwise a plain map of targets. Ignored
+    /// with `only_tail`
+    pub json_metadata: bool,
+}
+
+/// Writes `entries` in order of their targets, after `metadata` if there is any. Metrics and generators are only
+/// written to CSV and JSONL tables.
+pub fn write_table<W: Write>(
+    writer: W,
+    format: TableFormat,
+    entries: &[TableEntry],
+    metadata: Option<&TableMetadata>,
+    options: TableWriteOptions,
+) -> HexResult<()> {
+    if options.only_tail && format != TableFormat::Json {
+        return Err(HexError::InvalidTable("only JSON tables can store just the tails".into()));
+    }
+    check_generators(entries, metadata)?;
+
+    let mut sorted = Vec::from_iter(entries);
+    sorted.sort_by_key(|entry| entry.target);
+    if sorted.windows(2).any(|pair| pair[0].target == pair[1].target) {
+        return Err(HexError::InvalidTable("every target must be unique".into()));
+    }
+
+    match format {
+        TableFormat::Json => text::write_json(writer, &sorted, metadata, options),
+        TableFormat::Jsonl => text::write_jsonl(writer, &sorted, metadata),
+        TableFormat::Csv => text::write_csv(writer, &sorted, metadata),
+        TableFormat::Binary => binary::write(writer, &sorted, metadata),
+    }
+}
+
+/// Marks entries read from a format that always stores full patterns.
+fn with_patterns(
+    (entries, metadata): (Vec<TableEntry>, Option<TableMetadata>),
+) -> (Vec<TableEntry>, Option<TableMetadata>, bool) {
+    (entries, metadata, false)
+}
+
+/// Checks that every entry's generator is in the metadata.
+fn check_generators<'a>(
+    entries: impl IntoIterator<Item = &'a TableEntry>,
+    metadata: Option<&TableMetadata>,
+) -> HexResult<()> {
+    let num_generators = metadata.map_or(0, |metadata| metadata.generators.len());
+    match entries.into_iter().find(|entry| entry.generator.is_some_and(|generator| generator >= num_generators)) {
+        Some(entry) => Err(HexError::InvalidTable(format!(
+            "generator {} of `{}` isn't in the metadata",
+            entry.generator.unwrap_or_default(),
+            entry.target
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug)]
+enum Entries {
+    Parsed(BTreeMap<Ratio<i64>, TableEntry>),
+    Binary(BinaryTable),
+}
+
+/// A table of patterns that can be looked up by target.
+#[pyclass(module = "hexnumgen")]
+#[derive(Debug)]
+pub struct NumberTable {
+    format: TableFormat,
+    metadata: Option<TableMetadata>,
+    only_tail: bool,
+    entries: Entries,
+}
+
+impl NumberTable {
+    /// Opens a table, detecting its format from the file's contents or extension.
+    pub fn open(path: &FsPath) -> HexResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let format = match binary::has_magic(&mut reader)? {
+            true => TableFormat::Binary,
+            false => TableFormat::from_path(path),
+        };
+        Self::read(reader, format)
+    }
+
+    pub fn read<R: Read>(reader: R, format: TableFormat) -> HexResult<Self> {
+        let (entries, metadata, only_tail) = match format {
+            TableFormat::Json => text::read_json(reader)?,
+            TableFormat::Jsonl => with_patterns(text::read_jsonl(reader)?),
+            TableFormat::Csv => with_patterns(text::read_csv(reader)?),
+            TableFormat::Binary => {
+                let (table, metadata) = BinaryTable::read(reader)?;
+                return Ok(Self { format, metadata, only_tail: false, entries: Entries::Binary(table) });
+            }
+        };
+        check_generators(&entries, metadata.as_ref())?;
+
+        let mut map = BTreeMap::new();
+        for entry in entries {
+            if let Some(old) = map.insert(entry.target, entry) {
+                return Err(HexError::InvalidTable(format!("target `{}` appears more than once", old.target)));
+            }
+        }
+        Ok(Self { format, metadata, only_tail, entries: Entries::Parsed(map) })
+    }
+
+    pub fn format(&self) -> TableFormat {
+        self.format
+    }
+
+    /// Whether the table only held the tails of non-negative targets, like JSON tables written with `only_tail`.
+    pub fn only_tail(&self) -> bool {
+        self.only_tail
+    }
+
+    /// Where the table came from, or `None` for tables written before metadata was added.
+    pub fn metadata(&self) -> Option<&TableMetadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.entries {
+            Entries::Parsed(map) => map.len(),
+            Entries::Binary(table) => table.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the entry for `target`, or `None` if it isn't in the table.
+    pub fn get(&self, target: Ratio<i64>) -> HexResult<Option<TableEntry>> {
+        match &self.entries {
+            Entries::Parsed(map) => Ok(map.get(&target).cloned()),
+            Entries::Binary(table) => table.index_of(target).map(|index| table.entry(index)).transpose(),
+        }
+    }
+
+    /// Every entry in order of their targets.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = HexResult<TableEntry>> + '_> {
+        match &self.entries {
+            Entries::Parsed(map) => Box::new(map.values().cloned().map(Ok)),
+            Entries::Binary(table) => Box::new((0..table.len()).map(|index| table.entry(index))),
+        }
+    }
+}
+
+#[pymethods]
+impl NumberTable {
+    #[new]
+    fn py_new(path: PathBuf) -> PyResult<Self> {
+        Ok(Self::open(&path)?)
+    }
+
+    /// Returns the pattern for `target`, or `None` if it isn't in the table or failed to generate.
+    #[pyo3(name = "get")]
+    fn get_py(&self, target: PyRatio) -> PyResult<Option<GeneratedNumber>> {
+        let entry = self.get(target.try_into()?)?;
+        Ok(entry.map(|entry| entry.number()).transpose()?.flatten())
+    }
+
+    fn __contains__(&self, target: PyRatio) -> PyResult<bool> {
+        Ok(self.get(target.try_into()?)?.is_some())
+    }
+
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("NumberTable({} {} entries)", self.len(), self.format.extension())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AStarOptions, GenerationRequest, GeneratorOptions};
+
+    const FORMATS: [TableFormat; 4] = [TableFormat::Json, TableFormat::Jsonl, TableFormat::Csv, TableFormat::Binary];
+
+    fn metadata() -> TableMetadata {
+        let request = GenerationRequest::new(0.into(), GeneratorOptions::AStar(AStarOptions {}));
+        TableMetadata { runtime: 1.5, ..TableMetadata::new(vec![TableGenerator::from(&request)]) }
+    }
+
+    /// Two runs of negative targets and a run with a fractional step, with a gap before the last run and some failures.
+    fn entries() -> Vec<TableEntry> {
+        let entry = |numer, denom, pattern: Option<&str>| {
+            let target = Ratio::new(numer, denom);
+            TableEntry {
+                generator: pattern.map(|_| 0),
+                ..TableEntry::from_tail(target, pattern.map(|pattern| &pattern[4..]))
+            }
+        };
+        vec![
+            entry(-2, 1, Some("deddwa")),
+            entry(-7, 4, Some("deddwdadwdw")),
+            entry(0, 1, Some("aqaa")),
+            entry(1, 4, None),
+            entry(1, 2, Some("aqaawd")),
+            entry(3, 4, Some("aqaawdwd")),
+            entry(1, 1, Some("aqaaw")),
+            entry(5, 1, Some("aqaaq")),
+            entry(21, 4, None),
+            entry(11, 2, Some("aqaaqwd")),
+        ]
+        .into_iter()
+        .map(|entry| entry.with_metrics().unwrap())
+        .collect()
+    }
+
+    fn round_trip(format: TableFormat, entries: &[TableEntry], options: TableWriteOptions) -> NumberTable {
+        let mut bytes = Vec::new();
+        write_table(&mut bytes, format, entries, Some(&metadata()), options).unwrap();
+        NumberTable::read(bytes.as_slice(), format).unwrap()
+    }
+
+    /// What a format keeps of an entry, since only CSV and JSONL store metrics and generators.
+    fn stored(format: TableFormat, entry: &TableEntry) -> TableEntry {
+        match format {
+            TableFormat::Csv | TableFormat::Jsonl => entry.clone(),
+            TableFormat::Json | TableFormat::Binary => TableEntry::new(entry.target, entry.pattern.clone()),
+        }
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let entries = entries();
+        for format in FORMATS {
+            let table = round_trip(format, &entries, TableWriteOptions::default());
+            assert_eq!(table.format(), format);
+            assert_eq!(table.len(), entries.len(), "{format:?}");
+
+            let read: Vec<_> = table.iter().collect::<HexResult<_>>().unwrap();
+            let expected: Vec<_> = entries.iter().map(|entry| stored(format, entry)).collect();
+            assert_eq!(read, expected, "{format:?}");
+
+            for entry in &expected {
+                assert_eq!(table.get(entry.target).unwrap().as_ref(), Some(entry), "{format:?}");
+            }
+            for missing in [Ratio::new(-3, 2), Ratio::new(1, 8), 2.into(), Ratio::new(23, 4), 100.into()] {
+                assert_eq!(table.get(missing).unwrap(), None, "{format:?} {missing}");
+            }
+
+            match format {
+                TableFormat::Json => assert!(table.metadata().is_none()),
+                _ => {
+                    let metadata = table.metadata().unwrap();
+                    assert_eq!((metadata.runtime, metadata.generators.len()), (1.5, 1), "{format:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn json_tables_are_plain_maps_by_default() {
+        let entries = [
+            TableEntry::from_tail((-1).into(), Some("w")),
+            TableEntry::from_tail(0.into(), Some("")),
+            TableEntry::from_tail(1.into(), Some("w")),
+        ];
+        // the same serialization tables were written with before they had metadata
+        let plain: BTreeMap<i64, (&str, &str)> =
+            [(-1, ("NORTH_EAST", "deddw")), (0, ("SOUTH_EAST", "aqaa")), (1, ("SOUTH_EAST", "aqaaw"))].into();
+        for pretty in [false, true] {
+            let mut bytes = Vec::new();
+            let options = TableWriteOptions { pretty, ..Default::default() };
+            write_table(&mut bytes, TableFormat::Json, &entries, Some(&metadata()), options).unwrap();
+            let expected = match pretty {
+                true => serde_json::to_string_pretty(&plain).unwrap(),
+                false => serde_json::to_string(&plain).unwrap(),
+            };
+            assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn json_tables_can_store_metadata() {
+        let options = TableWriteOptions { json_metadata: true, ..Default::default() };
+        let table = round_trip(TableFormat::Json, &entries(), options);
+        let metadata = table.metadata().unwrap();
+        assert_eq!((metadata.runtime, metadata.generators.len()), (1.5, 1));
+        assert_eq!(table.len(), entries().len());
+
+        let options = TableWriteOptions { only_tail: true, json_metadata: true, ..Default::default() };
+        assert!(round_trip(TableFormat::Json, &entries(), options).metadata().is_none());
+    }
+
+    #[test]
+    fn json_tables_can_store_only_tails() {
+        let options = TableWriteOptions { only_tail: true, ..Default::default() };
+        let table = round_trip(TableFormat::Json, &entries(), options);
+        assert!(table.metadata().is_none());
+
+        let read: Vec<_> = table.iter().collect::<HexResult<_>>().unwrap();
+        let expected: Vec<_> = entries()
+            .iter()
+            .filter(|entry| entry.target >= 0.into())
+            .map(|entry| TableEntry::from_tail(entry.target, entry.tail()))
+            .collect();
+        assert_eq!(read, expected);
+
+        for format in [TableFormat::Jsonl, TableFormat::Csv, TableFormat::Binary] {
+            assert!(write_table(Vec::new(), format, &entries(), None, options).is_err());
+        }
+    }
+
+    #[test]
+    fn empty_tables_round_trip() {
+        for format in FORMATS {
+            let table = round_trip(format, &[], TableWriteOptions::default());
+            assert!(table.is_empty(), "{format:?}");
+            assert_eq!(table.get(0.into()).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        let duplicate = [TableEntry::from_tail(1.into(), Some("w")), TableEntry::from_tail(1.into(), None)];
+        let unknown_generator = [TableEntry { generator: Some(3), ..TableEntry::from_tail(1.into(), Some("w")) }];
+        for format in FORMATS {
+            assert!(write_table(Vec::new(), format, &duplicate, None, Default::default()).is_err());
+            assert!(write_table(Vec::new(), format, &unknown_generator, None, Default::default()).is_err());
+        }
+    }
+
+    #[test]
+    fn detects_only_tail_tables() {
+        let options = TableWriteOptions { only_tail: true, ..Default::default() };
+        assert!(round_trip(TableFormat::Json, &entries(), options).only_tail());
+        for format in FORMATS {
+            assert!(!round_trip(format, &entries(), TableWriteOptions::default()).only_tail(), "{format:?}");
+        }
+
+        let read = |json: &str| NumberTable::read(json.as_bytes(), TableFormat::Json).unwrap().only_tail();
+        assert!(read(r#"{"0": "", "1": "w", "2": null}"#));
+        assert!(!read(r#"{"1": ["SOUTH_EAST", "aqaaw"], "2": null}"#));
+        assert!(!read(r#"{"1": "w", "2": ["SOUTH_EAST", "aqaawa"]}"#));
+        assert!(!read(r#"{"2": null}"#));
+    }
+}
@@ -0,0 +1,299 @@
+//! JSON, JSONL and CSV tables.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read, Write},
+};
+
+use num_rational::Ratio;
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use super::{TableEntry, TableMetadata, TableMetrics, TableWriteOptions};
+use crate::{
+    errors::{HexError, HexResult},
+    hex_math::Direction,
+    numgen::Bounds,
+};
+
+/// A value in a JSON table: `[direction, pattern]`, or just the tail with `only_tail`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonValue {
+    Pattern(Direction, String),
+    Tail(String),
+}
+
+/// A JSON table as it's read, with the metadata under its own key next to the targets.
+#[derive(Deserialize)]
+struct JsonTable {
+    #[serde(default)]
+    metadata: Option<TableMetadata>,
+    #[serde(flatten)]
+    entries: BTreeMap<String, Option<JsonValue>>,
+}
+
+/// Serializes a JSON table's metadata, then its entries in numeric order, with keys like `5` or `5/4`.
+struct JsonMap<'a, V> {
+    metadata: Option<&'a TableMetadata>,
+    entries: Vec<(&'a Ratio<i64>, V)>,
+}
+
+impl<V: Serialize> Serialize for JsonMap<'_, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(metadata) = self.metadata {
+            map.serialize_entry("metadata", metadata)?;
+        }
+        for (target, value) in &self.entries {
+            map.serialize_entry(&target.to_string(), value)?;
+        }
+        map.end()
+    }
+}
+
+/// The first line of a JSONL table, if it has metadata.
+#[derive(Serialize)]
+struct JsonlHeader<'a> {
+    metadata: &'a TableMetadata,
+}
+
+/// One line of a JSONL table.
+#[derive(Serialize, Deserialize)]
+struct JsonlRecord {
+    #[serde(with = "crate::ratio_serde")]
+    target: Ratio<i64>,
+    direction: Option<Direction>,
+    pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generator: Option<usize>,
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    metrics: Option<TableMetrics>,
+}
+
+fn write_json_value<W: Write, T: Serialize>(writer: W, value: &T, pretty: bool) -> HexResult<()> {
+    Ok(match pretty {
+        true => serde_json::to_writer_pretty(writer, value),
+        false => serde_json::to_writer(writer, value),
+    }?)
+}
+
+/// Writes a JSON table. The metadata is left out unless `json_metadata` is set (and never written with `only_tail`),
+/// since JSON tables are loaded by consumers that expect every key to be a target.
+pub(super) fn write_json<W: Write>(
+    writer: W,
+    entries: &[&TableEntry],
+    metadata: Option<&TableMetadata>,
+    options: TableWriteOptions,
+) -> HexResult<()> {
+    if options.only_tail {
+        let tails = entries.iter().filter(|entry| entry.target >= 0.into()).map(|entry| (&entry.target, entry.tail()));
+        write_json_value(writer, &JsonMap { metadata: None, entries: tails.collect() }, options.pretty)
+    } else {
+        let patterns = entries.iter().map(|entry| (&entry.target, entry.pattern.as_ref()));
+        let metadata = metadata.filter(|_| options.json_metadata);
+        write_json_value(writer, &JsonMap { metadata, entries: patterns.collect() }, options.pretty)
+    }
+}
+
+/// Reads a JSON table, and whether every pattern in it was only a tail.
+pub(super) fn read_json<R: Read>(reader: R) -> HexResult<(Vec<TableEntry>, Option<TableMetadata>, bool)> {
+    let table: JsonTable = serde_json::from_reader(BufReader::new(reader))?;
+    let (mut tails, mut patterns) = (0, 0);
+    let entries = table
+        .entries
+        .into_iter()
+        .map(|(target, value)| {
+            let target = parse_target(&target).map_err(HexError::InvalidTable)?;
+            Ok(match value {
+                Some(JsonValue::Pattern(direction, pattern)) => {
+                    patterns += 1;
+                    TableEntry::new(target, Some((direction, pattern)))
+                }
+                Some(JsonValue::Tail(tail)) => {
+                    tails += 1;
+                    TableEntry::from_tail(target, Some(&tail))
+                }
+                None => TableEntry::new(target, None),
+            })
+        })
+        .collect::<HexResult<_>>()?;
+    Ok((entries, table.metadata, tails > 0 && patterns == 0))
+}
+
+/// Writes a JSONL table, with the metadata on the first line as `{"metadata": ...}`.
+pub(super) fn write_jsonl<W: Write>(
+    mut writer: W,
+    entries: &[&TableEntry],
+    metadata: Option<&TableMetadata>,
+) -> HexResult<()> {
+    if let Some(metadata) = metadata {
+        serde_json::to_writer(&mut writer, &JsonlHeader { metadata })?;
+        writer.write_all(b"\n")?;
+    }
+    for entry in entries {
+        let record = JsonlRecord {
+            target: entry.target,
+            direction: entry.pattern.as_ref().map(|(direction, _)| *direction),
+            pattern: entry.pattern.as_ref().map(|(_, pattern)| pattern.clone()),
+            generator: entry.generator,
+            metrics: entry.metrics,
+        };
+        serde_json::to_writer(&mut writer, &record)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(writer.flush()?)
+}
+
+pub(super) fn read_jsonl<R: Read>(reader: R) -> HexResult<(Vec<TableEntry>, Option<TableMetadata>)> {
+    let mut entries = Vec::new();
+    let mut metadata = None;
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |err: serde_json::Error| HexError::InvalidTable(format!("line {}: {err}", i + 1));
+
+        // only the first line can be metadata
+        if entries.is_empty()
+            && metadata.is_none()
+            && let Ok(Value::Object(mut object)) = serde_json::from_str(&line)
+            && let Some(value) = object.remove("metadata")
+        {
+            metadata = Some(serde_json::from_value(value).map_err(invalid)?);
+            continue;
+        }
+
+        let record: JsonlRecord = serde_json::from_str(&line).map_err(invalid)?;
+        entries.push(TableEntry {
+            target: record.target,
+            pattern: record.direction.zip(record.pattern),
+            metrics: record.metrics,
+            generator: record.generator,
+        });
+    }
+    Ok((entries, metadata))
+}
+
+const CSV_COLUMNS: [&str; 3] = ["target", "direction", "pattern"];
+const CSV_METRIC_COLUMNS: [&str; 6] = ["segments", "points", "q", "r", "s", "quasi_area"];
+const CSV_GENERATOR_COLUMN: &str = "generator";
+const CSV_METADATA_PREFIX: &str = "# metadata: ";
+
+/// Writes a CSV table, with the metadata in a comment before the header.
+pub(super) fn write_csv<W: Write>(
+    mut writer: W,
+    entries: &[&TableEntry],
+    metadata: Option<&TableMetadata>,
+) -> HexResult<()> {
+    if let Some(metadata) = metadata {
+        writeln!(writer, "{CSV_METADATA_PREFIX}{}", serde_json::to_string(metadata)?)?;
+    }
+
+    let with_metrics = entries.iter().any(|entry| entry.metrics.is_some());
+    let with_generators = entries.iter().any(|entry| entry.generator.is_some());
+    let mut header = CSV_COLUMNS.to_vec();
+    if with_metrics {
+        header.extend(CSV_METRIC_COLUMNS);
+    }
+    if with_generators {
+        header.push(CSV_GENERATOR_COLUMN);
+    }
+    writeln!(writer, "{}", header.join(","))?;
+
+    for entry in entries {
+        let (direction, pattern) = match &entry.pattern {
+            Some((direction, pattern)) => (direction.to_string(), pattern.as_str()),
+            None => (String::new(), ""),
+        };
+        write!(writer, "{},{direction},{pattern}", entry.target)?;
+        match (with_metrics, entry.metrics) {
+            (true, Some(TableMetrics { segments, points, bounds: Bounds { q, r, s }, quasi_area })) => {
+                write!(writer, ",{segments},{points},{q},{r},{s},{quasi_area}")?
+            }
+            (true, None) => write!(writer, ",,,,,,")?,
+            (false, _) => (),
+        }
+        match (with_generators, entry.generator) {
+            (true, Some(generator)) => write!(writer, ",{generator}")?,
+            (true, None) => write!(writer, ",")?,
+            (false, _) => (),
+        }
+        writeln!(writer)?;
+    }
+    Ok(writer.flush()?)
+}
+
+pub(super) fn read_csv<R: Read>(reader: R) -> HexResult<(Vec<TableEntry>, Option<TableMetadata>)> {
+    let mut lines = BufReader::new(reader).lines().enumerate();
+
+    // comments before the header are skipped, except the metadata
+    let mut metadata = None;
+    let mut header = String::new();
+    for (i, line) in lines.by_ref() {
+        let line = line?;
+        if !line.starts_with('#') {
+            header = line;
+            break;
+        }
+        if let Some(json) = line.strip_prefix(CSV_METADATA_PREFIX) {
+            let invalid =
+                |err: serde_json::Error| HexError::InvalidTable(format!("line {}: invalid metadata: {err}", i + 1));
+            metadata = Some(serde_json::from_str(json).map_err(invalid)?);
+        }
+    }
+    let columns: Vec<&str> = header.trim().split(',').collect();
+    let column = |name: &str| columns.iter().position(|column| *column == name);
+
+    let [target_col, direction_col, pattern_col] = CSV_COLUMNS.map(column);
+    let (Some(target_col), Some(direction_col), Some(pattern_col)) = (target_col, direction_col, pattern_col) else {
+        return Err(HexError::InvalidTable(format!("CSV header must have the columns {}", CSV_COLUMNS.join(","))));
+    };
+    let metric_cols: Option<Vec<usize>> = CSV_METRIC_COLUMNS.iter().map(|name| column(name)).collect();
+    let generator_col = column(CSV_GENERATOR_COLUMN);
+
+    let mut entries = Vec::new();
+    for (i, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |message: String| HexError::InvalidTable(format!("line {}: {message}", i + 1));
+        let fields: Vec<&str> = line.trim().split(',').collect();
+        let field = |index: usize| fields.get(index).copied().unwrap_or_default();
+
+        let target = parse_target(field(target_col)).map_err(invalid)?;
+        let pattern = match (field(direction_col), field(pattern_col)) {
+            ("", _) | (_, "") => None,
+            (direction, pattern) => {
+                Some((direction.parse().map_err(|err: HexError| invalid(err.to_string()))?, pattern.into()))
+            }
+        };
+
+        let metrics = match &metric_cols {
+            Some(cols) if !field(cols[0]).is_empty() => {
+                let values = cols
+                    .iter()
+                    .map(|&index| field(index).parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| invalid(format!("invalid metric: {err}")))?;
+                let bounds = Bounds::new(values[2] as u32, values[3] as u32, values[4] as u32);
+                Some(TableMetrics { segments: values[0], points: values[1], bounds, quasi_area: values[5] as u32 })
+            }
+            _ => None,
+        };
+
+        let generator = match generator_col.map(field) {
+            Some("") | None => None,
+            Some(generator) => Some(generator.parse().map_err(|err| invalid(format!("invalid generator: {err}")))?),
+        };
+
+        entries.push(TableEntry { target, pattern, metrics, generator });
+    }
+    Ok((entries, metadata))
+}
+
+fn parse_target(target: &str) -> Result<Ratio<i64>, String> {
+    target.trim().parse().map_err(|_| format!("invalid target `{target}`"))
+}
@@ -11,16 +11,34 @@ impl UnsignedAbsRatio<u64> for Ratio<i64> {
     }
 }
 
+pub trait DyadicRatio {
+    fn is_dyadic(&self) -> bool;
+}
+
+impl DyadicRatio for Ratio<i64> {
+    fn is_dyadic(&self) -> bool {
+        // Ratio keeps the denominator positive and reduced, so this is only true for powers of two
+        self.denom().count_ones() == 1
+    }
+}
+
 pub trait AbsDiffRatio {
-    fn abs_diff(self, other: Self) -> Self;
+    type Output;
+
+    fn abs_diff(self, other: Self) -> Self::Output;
 }
 
 impl AbsDiffRatio for Ratio<u64> {
-    fn abs_diff(self, other: Self) -> Self {
-        if self >= other {
-            self - other
+    type Output = Ratio<u128>;
+
+    fn abs_diff(self, other: Self) -> Ratio<u128> {
+        // finding a common denominator can overflow 64 bits, but never 128, so the difference is always exact
+        let widen = |value: Self| Ratio::new_raw(u128::from(*value.numer()), u128::from(*value.denom()));
+        let (a, b) = (widen(self), widen(other));
+        if a >= b {
+            a - b
         } else {
-            other - self
+            b - a
         }
     }
 }
@@ -49,3 +67,46 @@ impl<T> RwLockWriteIf<T> for RwLock<T> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abs_diff_is_symmetric() {
+        let (a, b) = (Ratio::new(7, 4), Ratio::new(1, 3));
+        assert_eq!(a.abs_diff(b), Ratio::new(17, 12));
+        assert_eq!(b.abs_diff(a), Ratio::new(17, 12));
+        assert_eq!(a.abs_diff(a), Ratio::from_integer(0));
+        assert_eq!(Ratio::new(3, 4).abs_diff(Ratio::new(1, 4)), Ratio::new(1, 2));
+    }
+
+    #[test]
+    fn abs_diff_is_exact_with_overflowing_denominators() {
+        let half = Ratio::new(1, 2);
+        let tiny = Ratio::new(1, (1 << 63) + 1);
+        assert_eq!(half.abs_diff(tiny), Ratio::new((1 << 63) - 1, (1 << 64) + 2));
+
+        let large = Ratio::new(u64::MAX, 3);
+        let small = Ratio::new(1, u64::MAX - 1);
+        let expected = Ratio::new(u128::from(u64::MAX), 3) - Ratio::new(1, u128::from(u64::MAX - 1));
+        assert_eq!(large.abs_diff(small), expected);
+    }
+
+    #[test]
+    fn abs_diff_orders_nearly_equal_distances() {
+        // these differ from the target by less than anything a 64-bit ratio could tell apart
+        let target = Ratio::new(1, 2);
+        let closer = Ratio::new(1 << 62, (1 << 63) + 1);
+        let further = Ratio::new(1 << 62, (1 << 63) + 3);
+        assert!(target.abs_diff(closer) < target.abs_diff(further));
+        assert!(closer.abs_diff(target) < further.abs_diff(target));
+    }
+
+    #[test]
+    fn dyadic_ratios() {
+        assert!(Ratio::new(3_i64, 8).is_dyadic());
+        assert!(Ratio::from_integer(5_i64).is_dyadic());
+        assert!(!Ratio::new(1_i64, 6).is_dyadic());
+    }
+}
@@ -1,7 +1,7 @@
 use itertools::Itertools;
 use num_integer::Integer;
-use num_rational::Ratio;
-use num_traits::Signed;
+use num_rational::{BigRational, Ratio};
+use num_traits::{Signed, ToPrimitive};
 use std::collections::HashSet;
 use std::hash::Hash;
 
@@ -66,3 +66,77 @@ pub fn drain_every_other<T>(items: &mut Vec<T>) -> Vec<T> {
 
     picked
 }
+
+/// Finds the dyadic rational (ie. a fraction with a power of two as its denominator) with the smallest denominator
+/// that's within `tolerance` of `value`, up to `max_denom`. These are the only fractions reachable by halving.
+pub fn approximate_dyadic(value: f64, tolerance: f64, max_denom: Option<u64>) -> Option<Ratio<i64>> {
+    for exponent in 0..i64::BITS - 1 {
+        let denom = 1_i64 << exponent;
+        if max_denom.is_some_and(|max_denom| denom.unsigned_abs() > max_denom) {
+            return None;
+        }
+
+        let numer = (value * denom as f64).round();
+
+        if !numer.is_finite() || numer.abs() >= i64::MAX as f64 {
+            return None;
+        }
+
+        if (numer / denom as f64 - value).abs() <= tolerance {
+            return Some(Ratio::new(numer as i64, denom));
+        }
+    }
+    None
+}
+
+/// Converts a float to the exact ratio it represents. Every finite float is a dyadic rational, but the exponent can
+/// need far more than 64 bits, so the conversion is done with big integers and only then narrowed. Returns `None` if
+/// the numerator doesn't fit in an `i64` or the denominator is larger than 2^62, eg. for `1e20` or `2^-70`.
+pub fn exact_ratio(value: f64) -> Option<Ratio<i64>> {
+    let ratio = BigRational::from_float(value)?;
+    Some(Ratio::new_raw(ratio.numer().to_i64()?, ratio.denom().to_i64()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approximate_dyadic_prefers_small_denominators() {
+        assert_eq!(approximate_dyadic(3.0, 0.0, None), Some(Ratio::from_integer(3)));
+        assert_eq!(approximate_dyadic(-0.5, 0.0, None), Some(Ratio::new(-1, 2)));
+        assert_eq!(approximate_dyadic(0.2, 0.25, None), Some(Ratio::from_integer(0)));
+        assert_eq!(approximate_dyadic(1.0 / 3.0, 0.01, None), Some(Ratio::new(21, 64)));
+    }
+
+    #[test]
+    fn approximate_dyadic_caps_denominator() {
+        assert_eq!(approximate_dyadic(1.0 / 3.0, 0.01, Some(32)), None);
+        assert_eq!(approximate_dyadic(1.0 / 3.0, 0.1, Some(4)), Some(Ratio::new(1, 4)));
+        assert_eq!(approximate_dyadic(2.4, 0.5, Some(1)), Some(Ratio::from_integer(2)));
+    }
+
+    #[test]
+    fn approximate_dyadic_rejects_unrepresentable_values() {
+        assert_eq!(approximate_dyadic(f64::NAN, 0.1, None), None);
+        assert_eq!(approximate_dyadic(f64::INFINITY, 0.1, None), None);
+        assert_eq!(approximate_dyadic(1e300, 0.1, None), None);
+    }
+
+    #[test]
+    fn exact_ratio_is_exact() {
+        assert_eq!(exact_ratio(-1.25), Some(Ratio::new(-5, 4)));
+        assert_eq!(exact_ratio(0.1), Some(Ratio::new(3602879701896397, 36028797018963968)));
+        assert_eq!(exact_ratio(2f64.powi(-62)), Some(Ratio::new(1, 1 << 62)));
+    }
+
+    #[test]
+    fn exact_ratio_rejects_values_outside_i64() {
+        assert_eq!(exact_ratio(1e20), None);
+        assert_eq!(exact_ratio(2f64.powi(-70)), None);
+        assert_eq!(exact_ratio(1e300), None);
+        assert_eq!(exact_ratio(5e-324), None);
+        assert_eq!(exact_ratio(2f64.powi(63)), None);
+        assert_eq!(exact_ratio(f64::NAN), None);
+    }
+}